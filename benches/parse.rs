@@ -0,0 +1,73 @@
+#[macro_use]
+extern crate criterion;
+extern crate igc;
+
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+use criterion::{Criterion, Throughput};
+
+use igc::{Reader, Record, BRecordRef, KRecordRef};
+use igc::utils::additions::AdditionDef;
+use igc::utils::lines::ByteLinesExt;
+
+const FIXTURES: &[&str] = &[
+    "05fd5hv1.igc",
+    "20090715.igc",
+    "2015-05-14-xlk-sem-01.igc",
+    "654g6ng1.igc",
+    "87el0ep1.igc",
+];
+
+/// Parses all lines into owned `Record`s, like most users of the crate do.
+fn parse_owned(bytes: &[u8]) -> usize {
+    Reader::new(Cursor::new(bytes)).filter_map(Result::ok).count()
+}
+
+/// Parses the B and K records into borrowed records, reusing a single line
+/// buffer and only allocating for the other record types.
+fn parse_borrowed(bytes: &[u8]) -> usize {
+    let mut lines = Cursor::new(bytes).byte_lines();
+    let mut b_addition_defs: Vec<AdditionDef> = Vec::new();
+    let mut k_addition_defs: Vec<AdditionDef> = Vec::new();
+    let mut count = 0;
+
+    while let Some(Ok((line, _))) = lines.next_line() {
+        let ok = match line.first() {
+            None => false,
+            Some(b'B') => BRecordRef::parse_with_additions(line, &b_addition_defs).is_ok(),
+            Some(b'K') => KRecordRef::parse_with_additions(line, &k_addition_defs).is_ok(),
+            Some(_) => match Record::parse(line) {
+                Ok(Record::I(record)) => { b_addition_defs = record.addition_defs; true },
+                Ok(Record::J(record)) => { k_addition_defs = record.addition_defs; true },
+                result => result.is_ok(),
+            },
+        };
+        if ok {
+            count += 1;
+        }
+    }
+
+    count
+}
+
+fn bench_fixtures(c: &mut Criterion) {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+
+    for name in FIXTURES {
+        let bytes = fs::read(fixtures.join(name)).unwrap();
+        assert_eq!(parse_owned(&bytes), parse_borrowed(&bytes));
+
+        let mut group = c.benchmark_group(*name);
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_function("owned", |b| b.iter(|| parse_owned(&bytes)));
+        group.bench_function("borrowed", |b| b.iter(|| parse_borrowed(&bytes)));
+        #[cfg(feature = "parallel")]
+        group.bench_function("parallel", |b| b.iter(|| igc::parallel::parse_bytes(&bytes, Default::default()).lines.len()));
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_fixtures);
+criterion_main!(benches);
@@ -0,0 +1,30 @@
+#[macro_use]
+extern crate criterion;
+extern crate igc;
+
+use criterion::Criterion;
+
+use igc::{BRecord, BRecordRef, DRecord, ERecord, FRecord, IRecord, JRecord, KRecord};
+use igc::utils::additions::{AdditionCode, AdditionDef};
+
+fn bench_records(c: &mut Criterion) {
+    let b_addition_defs = vec![
+        AdditionDef::new(AdditionCode::FXA, 36, 38),
+        AdditionDef::new(AdditionCode::SIU, 39, 40),
+    ];
+    let k_addition_defs = vec![AdditionDef::new(AdditionCode::HDT, 8, 10)];
+
+    let mut group = c.benchmark_group("records");
+    group.bench_function("B", |b| b.iter(|| BRecord::parse_with_additions(b"B1414065016925N00953112EA021640228700309", &b_addition_defs)));
+    group.bench_function("B (borrowed)", |b| b.iter(|| BRecordRef::parse_with_additions(b"B1414065016925N00953112EA021640228700309", &b_addition_defs)));
+    group.bench_function("D", |b| b.iter(|| DRecord::parse(b"D20001")));
+    group.bench_function("E", |b| b.iter(|| ERecord::parse(b"E104544ATS102312")));
+    group.bench_function("F", |b| b.iter(|| FRecord::parse(b"F1234560102ABC342")));
+    group.bench_function("I", |b| b.iter(|| IRecord::parse(b"I023638FXA3940SIU")));
+    group.bench_function("J", |b| b.iter(|| JRecord::parse(b"J010810HDT")));
+    group.bench_function("K", |b| b.iter(|| KRecord::parse_with_additions(b"K160310090", &k_addition_defs)));
+    group.finish();
+}
+
+criterion_group!(benches, bench_records);
+criterion_main!(benches);
@@ -0,0 +1,157 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use tokio::io::AsyncBufRead;
+
+use ::{ParseOptions, ParserState, Result, ParsedLine};
+use ::reader::LineParser;
+use ::utils::lines::AsyncByteLines;
+
+/// Same as the `Reader`, but reads the lines from an `AsyncBufRead` and
+/// yields the parsed lines as a `Stream`.
+///
+/// Only the current line is kept in memory, so a file that is still being
+/// received can be rejected as soon as the first invalid line arrives. Lines
+/// that are longer than `max_line_length()` are returned as an I/O error.
+///
+/// This type is only available with the `tokio` feature enabled.
+#[derive(Debug)]
+pub struct AsyncReader<B> {
+    lines: AsyncByteLines<B>,
+    parser: LineParser,
+}
+
+impl<B: AsyncBufRead + Unpin> AsyncReader<B> {
+    pub fn new(buf: B) -> AsyncReader<B> {
+        AsyncReader::with_options(buf, ParseOptions::default())
+    }
+
+    pub fn with_options(buf: B, options: ParseOptions) -> AsyncReader<B> {
+        AsyncReader {
+            lines: AsyncByteLines::new(buf),
+            parser: LineParser::new(options),
+        }
+    }
+
+    /// Limits the length of the lines, see `AsyncByteLines::max_line_length()`.
+    pub fn max_line_length(mut self, limit: usize) -> AsyncReader<B> {
+        self.lines = self.lines.max_line_length(limit);
+        self
+    }
+}
+
+impl<B> ParserState for AsyncReader<B> {
+    fn parser(&self) -> &LineParser {
+        &self.parser
+    }
+
+    fn parser_mut(&mut self) -> &mut LineParser {
+        &mut self.parser
+    }
+}
+
+impl<B: AsyncBufRead + Unpin> Stream for AsyncReader<B> {
+    type Item = Result<ParsedLine>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Result<ParsedLine>>> {
+        let reader = self.get_mut();
+        loop {
+            let (line, line_ending) = match reader.lines.poll_next_with_ending(cx) {
+                Poll::Ready(Some(Ok(line))) => line,
+                Poll::Ready(Some(Err(error))) => return Poll::Ready(Some(Err(error.into()))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            };
+
+            if let Some(result) = reader.parser.parse_line(&line, line_ending) {
+                return Poll::Ready(Some(result));
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::future::poll_fn;
+    use std::io::{self, Cursor};
+    use std::path::Path;
+    use std::thread;
+
+    use tokio::io::{AsyncWriteExt, BufReader, DuplexStream, duplex};
+    use tokio::runtime::{Builder, Runtime};
+
+    use super::*;
+    use ::{Error, ErrorKind, Reader, Record};
+
+    fn runtime() -> Runtime {
+        Builder::new_current_thread().build().unwrap()
+    }
+
+    fn next<B: AsyncBufRead + Unpin>(reader: &mut AsyncReader<B>) -> Option<Result<ParsedLine>> {
+        runtime().block_on(poll_fn(|cx| Pin::new(&mut *reader).poll_next(cx)))
+    }
+
+    /// Writes the bytes into the stream from another thread, in small parts
+    fn send(bytes: Vec<u8>) -> BufReader<DuplexStream> {
+        let (mut writer, reader) = duplex(64);
+        thread::spawn(move || runtime().block_on(writer.write_all(&bytes)).unwrap());
+        BufReader::new(reader)
+    }
+
+    #[test]
+    fn test_same_as_reader() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+        for entry in fs::read_dir(fixtures).unwrap() {
+            let bytes = fs::read(entry.unwrap().path()).unwrap();
+            for options in &[ParseOptions::default(), ParseOptions::lenient(), ParseOptions::strict()] {
+                let mut expected = Reader::with_options(Cursor::new(&bytes), *options);
+                let mut reader = AsyncReader::with_options(send(bytes.clone()), *options);
+
+                while let Some(line) = next(&mut reader) {
+                    match (line, expected.next().unwrap()) {
+                        (Ok(line), Ok(expected)) => assert_eq!(line, expected),
+                        (Err(error), Err(expected)) => assert_eq!(error.to_string(), expected.to_string()),
+                        (line, expected) => panic!("{:?} != {:?}", line, expected),
+                    }
+                }
+                assert!(expected.next().is_none());
+                assert_eq!(reader.warnings().len(), expected.warnings().len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_early_error() {
+        let (mut writer, stream) = duplex(1024);
+        runtime().block_on(writer.write_all(b"AXXX001\r\nB1414065016925N00953112EA021640228\r\n")).unwrap();
+
+        // the writer is still open, so the rest of the file is not known yet
+        let mut reader = AsyncReader::new(BufReader::new(stream));
+        match next(&mut reader).unwrap().unwrap().record {
+            Some(Record::A(ref record)) => assert_eq!(record.manufacturer, "XXX"),
+            ref record => panic!("unexpected record: {:?}", record),
+        }
+        let error = next(&mut reader).unwrap().unwrap_err();
+        assert_eq!(error.kind(), Some(ErrorKind::BadAltitude));
+
+        drop(writer);
+        assert!(next(&mut reader).is_none());
+    }
+
+    #[test]
+    fn test_max_line_length() {
+        let mut bytes = b"AXXX001\r\nLPLT".to_vec();
+        bytes.extend_from_slice(&[b'x'; 1000]);
+        bytes.extend_from_slice(b"\r\n");
+
+        let mut reader = AsyncReader::new(send(bytes)).max_line_length(100);
+        assert!(next(&mut reader).unwrap().is_ok());
+        match next(&mut reader).unwrap() {
+            Err(Error::Io(ref error)) => assert_eq!(error.kind(), io::ErrorKind::InvalidData),
+            line => panic!("unexpected line: {:?}", line),
+        }
+    }
+}
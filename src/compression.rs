@@ -0,0 +1,303 @@
+//! Reading of gzip compressed IGC files and of zip archives with several
+//! IGC files in them
+//!
+//! This module is only available with the `compression` feature enabled.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Read, Seek};
+use std::path::Path;
+
+use flate2::bufread::MultiGzDecoder;
+use zip::ZipArchive;
+
+use ::Result;
+
+/// Format of a file, detected by its magic bytes
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Format {
+    /// Anything that is neither gzip nor zip, e.g. an uncompressed IGC file
+    Plain,
+    Gzip,
+    Zip,
+}
+
+impl Format {
+    /// Detects the format from the first bytes of a file.
+    pub fn detect(bytes: &[u8]) -> Format {
+        if bytes.starts_with(b"\x1f\x8b") {
+            Format::Gzip
+        } else if bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(b"PK\x05\x06") {
+            Format::Zip
+        } else {
+            Format::Plain
+        }
+    }
+}
+
+/// Name of a file and a reader for its uncompressed content
+pub type Entry = (String, Box<dyn BufRead + Send>);
+
+/// Default for `Entries::max_entry_size()`
+pub const DEFAULT_MAX_ENTRY_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Files of an archive or compressed file that was opened with `open()`
+pub struct Entries<R> {
+    inner: Inner<R>,
+    max_entry_size: u64,
+}
+
+enum Inner<R> {
+    Single(Option<Entry>),
+    Gzip(Option<(String, BufReader<R>)>),
+    Zip { archive: ZipArchive<BufReader<R>>, index: usize },
+}
+
+/// Reader that returns an error instead of more than `limit` bytes
+struct Limited<R> {
+    inner: io::Take<R>,
+    name: String,
+    limit: u64,
+}
+
+impl<R: Read> Limited<R> {
+    fn new(inner: R, name: String, limit: u64) -> Limited<R> {
+        // one more byte is read to tell a file of exactly `limit` bytes from
+        // a larger one
+        Limited { inner: inner.take(limit.saturating_add(1)), name, limit }
+    }
+}
+
+impl<R: Read> Read for Limited<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let length = self.inner.read(buf)?;
+        if self.inner.limit() == 0 {
+            let message = format!("{} is larger than {} bytes", self.name, self.limit);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        }
+        Ok(length)
+    }
+}
+
+/// Detects if the `reader` is gzip compressed, a zip archive or neither and
+/// returns the files in it.
+///
+/// Plain files are returned unchanged as a single entry named `name`.
+/// Gzip files are also returned as a single entry, with the `.gz` extension
+/// removed from `name`. For zip archives, all files are returned with their
+/// path in the archive, but directories are skipped.
+pub fn open<R: Read + Seek + Send + 'static>(name: &str, reader: R) -> Result<Entries<R>> {
+    let mut reader = BufReader::new(reader);
+
+    let inner = match Format::detect(reader.fill_buf()?) {
+        Format::Plain => Inner::Single(Some((name.to_owned(), Box::new(reader)))),
+        Format::Gzip => {
+            let name = name.strip_suffix(".gz").unwrap_or(name).to_owned();
+            Inner::Gzip(Some((name, reader)))
+        },
+        Format::Zip => Inner::Zip {
+            archive: ZipArchive::new(reader).map_err(io::Error::from)?,
+            index: 0,
+        },
+    };
+
+    Ok(Entries { inner, max_entry_size: DEFAULT_MAX_ENTRY_SIZE })
+}
+
+/// Same as `open()` for the file at `path`, using its file name.
+pub fn open_path<P: AsRef<Path>>(path: P) -> Result<Entries<File>> {
+    let path = path.as_ref();
+    let name = path.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    open(&name, File::open(path)?)
+}
+
+impl<R> Entries<R> {
+    /// Limits the uncompressed size of the files in zip archives, which are
+    /// decompressed into memory, and of gzip files. Larger files in zip
+    /// archives are returned as an error instead, and the reader of a larger
+    /// gzip file returns an error once it reaches the limit. The default is
+    /// `DEFAULT_MAX_ENTRY_SIZE`.
+    pub fn max_entry_size(mut self, limit: u64) -> Entries<R> {
+        self.max_entry_size = limit;
+        self
+    }
+}
+
+impl<R: Read + Seek + Send + 'static> Iterator for Entries<R> {
+    type Item = Result<Entry>;
+
+    /// Returns the next file. Files in zip archives are decompressed into
+    /// memory one at a time, since the archive can't be read while one of
+    /// its files is being read.
+    fn next(&mut self) -> Option<Result<Entry>> {
+        match self.inner {
+            Inner::Single(ref mut entry) => entry.take().map(Ok),
+            Inner::Gzip(ref mut entry) => entry.take().map(|(name, reader)| {
+                let decoder = Limited::new(MultiGzDecoder::new(reader), name.clone(), self.max_entry_size);
+                Ok((name, Box::new(BufReader::new(decoder)) as Box<dyn BufRead + Send>))
+            }),
+            Inner::Zip { ref mut archive, ref mut index } => {
+                while *index < archive.len() {
+                    let mut file = match archive.by_index(*index) {
+                        Ok(file) => file,
+                        Err(error) => return Some(Err(io::Error::from(error).into())),
+                    };
+                    *index += 1;
+
+                    if file.is_dir() {
+                        continue;
+                    }
+
+                    // the size in the archive can't be trusted, so it is only
+                    // used as a hint and the reading stops after the limit
+                    let limit = self.max_entry_size;
+                    let mut bytes = Vec::with_capacity(file.size().min(limit) as usize);
+                    if let Err(error) = file.by_ref().take(limit.saturating_add(1)).read_to_end(&mut bytes) {
+                        return Some(Err(error.into()));
+                    }
+                    if bytes.len() as u64 > limit {
+                        let message = format!("{} is larger than {} bytes", file.name(), limit);
+                        return Some(Err(io::Error::new(io::ErrorKind::InvalidData, message).into()));
+                    }
+                    return Some(Ok((file.name().to_owned(), Box::new(Cursor::new(bytes)))));
+                }
+                None
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use zip::ZipWriter;
+    use zip::write::SimpleFileOptions;
+
+    use super::*;
+    use ::{IgcFile, Reader};
+    use ::utils::lines::ByteLinesExt;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(name)
+    }
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn read_all(entries: Entries<Cursor<Vec<u8>>>) -> Vec<(String, Vec<u8>)> {
+        entries.map(|entry| {
+            let (name, mut reader) = entry.unwrap();
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).unwrap();
+            (name, bytes)
+        }).collect()
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Format::detect(b""), Format::Plain);
+        assert_eq!(Format::detect(b"AXXX001"), Format::Plain);
+        assert_eq!(Format::detect(&gzip(b"AXXX001")), Format::Gzip);
+        assert_eq!(Format::detect(b"PK\x03\x04\x14\x00"), Format::Zip);
+    }
+
+    #[test]
+    fn test_plain_and_gzip() {
+        let bytes = fs::read(fixture("654g6ng1.igc")).unwrap();
+
+        let entries = read_all(open("654g6ng1.igc", Cursor::new(bytes.clone())).unwrap());
+        assert_eq!(entries, vec![("654g6ng1.igc".to_owned(), bytes.clone())]);
+
+        let entries = read_all(open("654g6ng1.igc.gz", Cursor::new(gzip(&bytes))).unwrap());
+        assert_eq!(entries, vec![("654g6ng1.igc".to_owned(), bytes)]);
+    }
+
+    #[test]
+    fn test_zip() {
+        let first = fs::read(fixture("654g6ng1.igc")).unwrap();
+        let second = fs::read(fixture("87el0ep1.igc")).unwrap();
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.add_directory("flights/", SimpleFileOptions::default()).unwrap();
+        writer.start_file("flights/654g6ng1.igc", SimpleFileOptions::default()).unwrap();
+        writer.write_all(&first).unwrap();
+        writer.start_file("87el0ep1.igc", SimpleFileOptions::default()).unwrap();
+        writer.write_all(&second).unwrap();
+        let archive = writer.finish().unwrap().into_inner();
+
+        let entries = read_all(open("flights.zip", Cursor::new(archive)).unwrap());
+        assert_eq!(entries, vec![
+            ("flights/654g6ng1.igc".to_owned(), first),
+            ("87el0ep1.igc".to_owned(), second),
+        ]);
+    }
+
+    #[test]
+    fn test_max_entry_size() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.start_file("large.igc", SimpleFileOptions::default()).unwrap();
+        writer.write_all(&[b'L'; 101]).unwrap();
+        writer.start_file("small.igc", SimpleFileOptions::default()).unwrap();
+        writer.write_all(&[b'L'; 100]).unwrap();
+        let archive = writer.finish().unwrap().into_inner();
+
+        let mut entries = open("flights.zip", Cursor::new(archive)).unwrap().max_entry_size(100);
+        let error = entries.next().unwrap().err().unwrap();
+        assert_eq!(error.to_string(), "large.igc is larger than 100 bytes");
+        assert_eq!(entries.next().unwrap().unwrap().0, "small.igc");
+        assert!(entries.next().is_none());
+
+        for &(size, is_ok) in &[(100, true), (101, false)] {
+            let mut entries = open("large.igc.gz", Cursor::new(gzip(&vec![b'L'; size]))).unwrap().max_entry_size(100);
+            let (_, mut reader) = entries.next().unwrap().unwrap();
+            let mut bytes = Vec::new();
+            match reader.read_to_end(&mut bytes) {
+                Ok(length) => assert!(is_ok && length == size),
+                Err(error) => {
+                    assert!(!is_ok);
+                    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+                    assert_eq!(error.to_string(), "large.igc is larger than 100 bytes");
+                },
+            }
+        }
+    }
+
+    #[test]
+    fn test_send() {
+        // the entries can be read on another thread
+        let bytes = gzip(&fs::read(fixture("654g6ng1.igc")).unwrap());
+        let (_, reader) = open("654g6ng1.igc.gz", Cursor::new(bytes)).unwrap().next().unwrap().unwrap();
+        assert_eq!(::std::thread::spawn(move || reader.byte_lines().count()).join().unwrap(), 13533);
+    }
+
+    #[test]
+    fn test_readers() {
+        let bytes = gzip(&fs::read(fixture("654g6ng1.igc")).unwrap());
+
+        let (_, reader) = open("654g6ng1.igc.gz", Cursor::new(bytes.clone())).unwrap().next().unwrap().unwrap();
+        assert_eq!(reader.byte_lines().count(), 13533);
+
+        let (_, reader) = open("654g6ng1.igc.gz", Cursor::new(bytes.clone())).unwrap().next().unwrap().unwrap();
+        assert!(Reader::new(reader).all(|line| line.is_ok()));
+
+        let (_, reader) = open("654g6ng1.igc.gz", Cursor::new(bytes)).unwrap().next().unwrap().unwrap();
+        assert_eq!(IgcFile::parse(reader).unwrap().fixes().len(), 9762);
+    }
+
+    #[test]
+    fn test_open_path() {
+        let mut entries = open_path(fixture("87el0ep1.igc")).unwrap();
+        assert_eq!(entries.next().unwrap().unwrap().0, "87el0ep1.igc");
+        assert!(entries.next().is_none());
+
+        assert!(open_path(fixture("missing.igc")).is_err());
+    }
+}
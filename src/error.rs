@@ -1,22 +1,189 @@
 use std::fmt;
+#[cfg(feature = "std")]
+use std::io;
 
-#[derive(Debug, Fail)]
+use ::prelude::*;
+
+#[derive(Debug)]
 pub enum Error {
-    #[fail(display = "invalid record: {}", line)]
-    InvalidRecord { line: Line },
+    Parse(ParseError),
+
+    /// Only available with the `std` feature
+    #[cfg(feature = "std")]
+    Io(io::Error),
 }
 
+// without `std` there are only parse errors
+#[cfg_attr(not(feature = "std"), allow(irrefutable_let_patterns))]
 impl Error {
+    /// Line that does not match the format of its record type
     pub fn invalid_record(line: &[u8]) -> Error {
-        Error::InvalidRecord { line: line.into() }
+        Error::parse(ErrorKind::InvalidRecord, line)
+    }
+
+    pub fn parse(kind: ErrorKind, line: &[u8]) -> Error {
+        Error::Parse(ParseError {
+            kind,
+            line: line.into(),
+            line_number: None,
+            column: None,
+            field: None,
+        })
+    }
+
+    /// Attaches the (1-indexed) byte column and the name of the field that
+    /// caused a parse error.
+    pub fn in_field<S: Into<String>>(mut self, column: usize, field: S) -> Error {
+        if let Error::Parse(ref mut error) = self {
+            error.column = Some(column);
+            error.field = Some(field.into());
+        }
+        self
+    }
+
+    /// Attaches the (1-indexed) line number of the file to a parse error.
+    pub fn on_line(mut self, line_number: usize) -> Error {
+        if let Error::Parse(ref mut error) = self {
+            error.line_number = Some(line_number);
+        }
+        self
+    }
+
+    /// Kind of the parse error, or `None` for I/O errors
+    pub fn kind(&self) -> Option<ErrorKind> {
+        match *self {
+            Error::Parse(ref error) => Some(error.kind),
+            #[cfg(feature = "std")]
+            Error::Io(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Parse(ref error) => error.fmt(f),
+            #[cfg(feature = "std")]
+            Error::Io(ref error) => error.fmt(f),
+        }
     }
 }
 
-pub type Result<T> = std::result::Result<T, Error>;
+#[cfg(feature = "std")]
+impl ::failure::Fail for Error {
+    fn cause(&self) -> Option<&dyn (::failure::Fail)> {
+        match *self {
+            Error::Parse(_) => None,
+            Error::Io(ref error) => Some(error),
+        }
+    }
+}
 
-#[derive(Debug)]
+#[cfg(feature = "std")]
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Error {
+        Error::Io(error)
+    }
+}
+
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+/// What exactly is wrong with a line that could not be parsed
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ErrorKind {
+    /// The line does not match the format of its record type
+    InvalidRecord,
+    /// The first byte of the line is not a known record type
+    UnknownRecordType,
+    BadTime,
+    BadDate,
+    BadCoordinate,
+    BadAltitude,
+    BadNumber,
+    /// The byte range of an addition definition overlaps another one or does
+    /// not fit into the record, or an addition value does not fit its range
+    AdditionOutOfRange,
+    /// A B or K record is too short for an addition that the I or J record
+    /// defines, or there is no value for a defined addition
+    MissingAddition,
+    /// The number of additions of an I or J record does not match the
+    /// number of addition definitions that follow it
+    AdditionCountMismatch,
+    /// Text that is neither valid UTF-8 nor Latin-1
+    BadEncoding,
+}
+
+impl ErrorKind {
+    pub fn description(&self) -> &'static str {
+        match *self {
+            ErrorKind::InvalidRecord => "invalid record",
+            ErrorKind::UnknownRecordType => "unknown record type",
+            ErrorKind::BadTime => "bad time",
+            ErrorKind::BadDate => "bad date",
+            ErrorKind::BadCoordinate => "bad coordinate",
+            ErrorKind::BadAltitude => "bad altitude",
+            ErrorKind::BadNumber => "bad number",
+            ErrorKind::AdditionOutOfRange => "addition out of range",
+            ErrorKind::MissingAddition => "missing addition",
+            ErrorKind::AdditionCountMismatch => "addition count mismatch",
+            ErrorKind::BadEncoding => "bad encoding",
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.description())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub kind: ErrorKind,
+    pub line: Line,
+
+    /// Line number in the file (1-indexed!), if the line was read by a `Reader`
+    pub line_number: Option<usize>,
+
+    /// Byte column of the field in the line (1-indexed!)
+    pub column: Option<usize>,
+
+    /// Name of the field that could not be parsed
+    pub field: Option<String>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(line_number) = self.line_number {
+            write!(f, "line {}: ", line_number)?;
+        }
+
+        write!(f, "{}", self.kind)?;
+
+        if let Some(column) = self.column {
+            write!(f, " at column {}", column)?;
+        }
+
+        if let Some(ref field) = self.field {
+            write!(f, " ({})", field)?;
+        }
+
+        write!(f, ": {}", self.line)
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::failure::Fail for ParseError {}
+
+#[derive(Debug, Clone)]
 pub struct Line(Vec<u8>);
 
+impl Line {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 impl <'a> From<&'a [u8]> for Line {
     fn from(bytes: &'a [u8]) -> Self {
         Line(bytes.to_vec())
@@ -24,12 +191,30 @@ impl <'a> From<&'a [u8]> for Line {
 }
 
 impl fmt::Display for Line {
-    fn fmt(&self, f: &mut fmt::Formatter) -> std::result::Result<(), fmt::Error> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let bytes = self.0.as_ref();
 
-        match std::str::from_utf8(bytes) {
+        match ::std::str::from_utf8(bytes) {
             Ok(s) => f.write_str(s),
             Err(_) => write!(f, "{:?}", bytes),
         }
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let error = Error::invalid_record(b"X123");
+        assert_eq!(error.to_string(), "invalid record: X123");
+        assert_eq!(error.kind(), Some(ErrorKind::InvalidRecord));
+
+        let error = Error::parse(ErrorKind::BadCoordinate, b"B1234565016925X")
+            .in_field(8, "latitude")
+            .on_line(12);
+        assert_eq!(error.to_string(), "line 12: bad coordinate at column 8 (latitude): B1234565016925X");
+    }
+}
@@ -0,0 +1,289 @@
+use std::io::BufRead;
+use std::ops::Deref;
+
+use ::{Result, ParseError, ParseOptions, ParserState, SliceReader, Record, Date, DateTime, ARecord, BRecord, CRecord, DRecord, ERecord, FRecord, GRecord, HRecord,
+       IRecord, JRecord, KRecord, LRecord};
+use ::task::Task;
+use ::utils::headers::{HeaderCode, HeaderValue};
+use ::utils::text::Encoding;
+
+/// Record with its full UTC date and time, if the date of the file is known
+#[derive(Debug)]
+pub struct Timestamped<T> {
+    pub datetime: Option<DateTime>,
+    pub record: T,
+}
+
+impl<T> Deref for Timestamped<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.record
+    }
+}
+
+/// In-memory representation of a complete IGC file
+#[derive(Debug, Default)]
+pub struct IgcFile {
+    a_record: Option<ARecord>,
+    headers: Vec<HRecord>,
+    task: Option<Task>,
+    fixes: Vec<Timestamped<BRecord>>,
+    dgps: Vec<DRecord>,
+    events: Vec<Timestamped<ERecord>>,
+    satellites: Vec<Timestamped<FRecord>>,
+    i_record: Option<IRecord>,
+    j_record: Option<JRecord>,
+    k_records: Vec<Timestamped<KRecord>>,
+    comments: Vec<LRecord>,
+    security: Vec<GRecord>,
+    warnings: Vec<ParseError>,
+    encoding: Encoding,
+}
+
+impl IgcFile {
+    /// Reads and parses all records of an IGC file.
+    pub fn parse<B: BufRead>(buf: B) -> Result<IgcFile> {
+        IgcFile::parse_with_options(buf, ParseOptions::default())
+    }
+
+    /// Reads and parses all records of an IGC file, using the given
+    /// `ParseOptions`. In lenient mode, all problems are collected in
+    /// `warnings()` instead.
+    ///
+    /// Unless the options specify an encoding, it is detected from the
+    /// complete file before the text fields are decoded. The `Reader` only
+    /// detects it from the first line with non-ASCII characters, see its
+    /// documentation.
+    pub fn parse_with_options<B: BufRead>(mut buf: B, options: ParseOptions) -> Result<IgcFile> {
+        let mut bytes = Vec::new();
+        buf.read_to_end(&mut bytes)?;
+        IgcFile::parse_bytes_with_options(&bytes, options)
+    }
+
+    pub fn parse_bytes(bytes: &[u8]) -> Result<IgcFile> {
+        IgcFile::parse_bytes_with_options(bytes, ParseOptions::default())
+    }
+
+    /// Same as `parse_with_options()` for a file that is already in memory.
+    pub fn parse_bytes_with_options(bytes: &[u8], options: ParseOptions) -> Result<IgcFile> {
+        let encoding = options.encoding.unwrap_or_else(|| Encoding::detect(bytes));
+        let mut file = IgcFile { encoding, ..IgcFile::default() };
+        let mut reader = SliceReader::with_options(bytes, options.encoding(encoding));
+        for line in &mut reader {
+            let line = line?;
+            if let Some(record) = line.record {
+                file.push(record, line.datetime);
+            }
+        }
+        file.warnings = reader.take_warnings();
+        Ok(file)
+    }
+
+    /// Adds a record to the file. The `datetime` is only used for B, E, F
+    /// and K records.
+    ///
+    /// C record turnpoints are added to the task of the most recent C record
+    /// declaration and are ignored if there is no such declaration.
+    pub fn push(&mut self, record: Record, datetime: Option<DateTime>) {
+        match record {
+            Record::A(record) => self.a_record = Some(record),
+            Record::B(record) => self.fixes.push(Timestamped { datetime, record }),
+            Record::C(CRecord::Declaration(record)) => self.task = Some(Task::new(record)),
+            Record::C(CRecord::Turnpoint(record)) => if let Some(ref mut task) = self.task {
+                task.points.push(record);
+            },
+            Record::D(record) => self.dgps.push(record),
+            Record::E(record) => self.events.push(Timestamped { datetime, record }),
+            Record::F(record) => self.satellites.push(Timestamped { datetime, record }),
+            Record::G(record) => self.security.push(record),
+            Record::H(record) => self.headers.push(record),
+            Record::I(record) => self.i_record = Some(record),
+            Record::J(record) => self.j_record = Some(record),
+            Record::K(record) => self.k_records.push(Timestamped { datetime, record }),
+            Record::L(record) => self.comments.push(record),
+        }
+    }
+
+    /// FR manufacturer and FR serial number
+    pub fn a_record(&self) -> Option<&ARecord> {
+        self.a_record.as_ref()
+    }
+
+    pub fn headers(&self) -> &[HRecord] {
+        &self.headers
+    }
+
+    /// First header with the given code
+    pub fn header(&self, code: HeaderCode) -> Option<&HRecord> {
+        self.headers.iter().find(|header| header.code == code)
+    }
+
+    /// Typed value of the first header with the given code
+    pub fn header_value(&self, code: HeaderCode) -> Option<HeaderValue> {
+        self.header(code).map(HRecord::value)
+    }
+
+    /// UTC date of the flight from the `DTE` header
+    pub fn date(&self) -> Option<Date> {
+        match self.header_value(HeaderCode::DTE) {
+            Some(HeaderValue::Date { date, .. }) => Some(date),
+            _ => None,
+        }
+    }
+
+    pub fn task(&self) -> Option<&Task> {
+        self.task.as_ref()
+    }
+
+    pub fn fixes(&self) -> &[Timestamped<BRecord>] {
+        &self.fixes
+    }
+
+    pub fn dgps(&self) -> &[DRecord] {
+        &self.dgps
+    }
+
+    pub fn events(&self) -> &[Timestamped<ERecord>] {
+        &self.events
+    }
+
+    pub fn satellites(&self) -> &[Timestamped<FRecord>] {
+        &self.satellites
+    }
+
+    /// B record addition definitions
+    pub fn i_record(&self) -> Option<&IRecord> {
+        self.i_record.as_ref()
+    }
+
+    /// K record addition definitions
+    pub fn j_record(&self) -> Option<&JRecord> {
+        self.j_record.as_ref()
+    }
+
+    pub fn k_records(&self) -> &[Timestamped<KRecord>] {
+        &self.k_records
+    }
+
+    pub fn comments(&self) -> &[LRecord] {
+        &self.comments
+    }
+
+    /// Security records (G records) in the order of the file
+    pub fn security(&self) -> &[GRecord] {
+        &self.security
+    }
+
+    /// Problems that were found while parsing the file in lenient mode
+    pub fn warnings(&self) -> &[ParseError] {
+        &self.warnings
+    }
+
+    /// Encoding that was used to decode the text fields
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::{Date, ErrorKind, Time, ParsedLine};
+    use ::utils::additions::AdditionSupport;
+
+    const EXAMPLE: &[u8] = b"AFLA6NG\r
+HFDTE040516\r
+HFGIDGliderID:D-KCSS\r
+I023638FXA3940SIU\r
+C040516072924000000000002Task\r
+C0000000N00000000ET\r
+C5049383N00611183E1\r
+B0810425049315N00610899EA000480023000208\r
+E104533PEV\r
+B0810505049315N00610899EA000480023000208\r
+LPLTThis flight was my second 1000km attempt\r
+G60DC059E2D2F6CAD2E889224E355DBDDB805CAB10000\r
+";
+
+    #[test]
+    fn test_parse() {
+        let file = IgcFile::parse_bytes(EXAMPLE).unwrap();
+
+        let a_record = file.a_record().unwrap();
+        assert_eq!(a_record.manufacturer, "FLA");
+        assert_eq!(a_record.id, "6NG");
+
+        assert_eq!(file.headers().len(), 2);
+        assert_eq!(file.header(HeaderCode::GID).unwrap().text, "D-KCSS");
+        assert!(file.header(HeaderCode::PLT).is_none());
+        assert_eq!(file.date(), Some(Date::new(16, 5, 4)));
+
+        let task = file.task().unwrap();
+        assert_eq!(task.declaration.num_turnpoints, 2);
+        assert_eq!(task.points.len(), 2);
+        assert!(!task.is_consistent());
+        assert!(task.takeoff().is_none());
+        assert_eq!(task.start().unwrap().text, Some("1".into()));
+
+        assert_eq!(file.fixes().len(), 2);
+        assert_eq!(file.fixes()[0].fix_accuracy(), Some(2));
+        assert_eq!(file.fixes()[0].datetime, Some(DateTime::new(Date::new(16, 5, 4), Time::from_hms(8, 10, 42))));
+        assert_eq!(file.events().len(), 1);
+        assert_eq!(file.comments().len(), 1);
+        assert_eq!(file.security().len(), 1);
+        assert_eq!(file.i_record().unwrap().num_additions, 2);
+        assert!(file.j_record().is_none());
+        assert!(file.k_records().is_empty());
+        assert!(file.satellites().is_empty());
+        assert!(file.dgps().is_empty());
+    }
+
+    #[test]
+    fn test_encoding() {
+        assert_eq!(IgcFile::parse_bytes(EXAMPLE).unwrap().encoding(), Encoding::Utf8);
+
+        // the second comment decides the encoding of the first one
+        let bytes = b"LPLTb\xe8\nLPLT\x8aebesta\n";
+        let file = IgcFile::parse_bytes(bytes).unwrap();
+        assert_eq!(file.encoding(), Encoding::Windows1250);
+        assert_eq!(file.comments()[0].text, "b\u{10d}");
+
+        // the reader can't look ahead and decides on the first comment,
+        // unless it gets the encoding of the whole file
+        let comment = |options: ParseOptions| match SliceReader::with_options(bytes, options).next() {
+            Some(Ok(ParsedLine { record: Some(Record::L(record)), .. })) => record.text,
+            line => panic!("unexpected line: {:?}", line),
+        };
+        assert_eq!(comment(ParseOptions::default()), "b\u{e8}");
+        assert_eq!(comment(ParseOptions::default().encoding(Encoding::detect(bytes))), "b\u{10d}");
+
+        let options = ParseOptions::default().encoding(Encoding::Latin1);
+        let file = IgcFile::parse_with_options(&b"LPLTb\xe8\n"[..], options).unwrap();
+        assert_eq!(file.encoding(), Encoding::Latin1);
+        assert_eq!(file.comments()[0].text, "b\u{e8}");
+
+        let path = ::std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/87el0ep1.igc");
+        let file = IgcFile::parse(::std::io::BufReader::new(::std::fs::File::open(path).unwrap())).unwrap();
+        assert_eq!(file.encoding(), Encoding::Latin1);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(IgcFile::parse_bytes(b"AFLA6NG\nB123\n").is_err());
+    }
+
+    #[test]
+    fn test_addition_out_of_range() {
+        for input in &[&b"I010003FXA\nB1414065016925N00953112EA021640228700\n"[..], b"I013836FXA\nB1414065016925N00953112EA021640228700\n"] {
+            let error = IgcFile::parse_bytes(input).unwrap_err();
+            assert_eq!(error.kind(), Some(ErrorKind::AdditionOutOfRange));
+
+            let file = IgcFile::parse_bytes_with_options(input, ParseOptions::lenient()).unwrap();
+            assert_eq!(file.fixes().len(), 1);
+            assert_eq!(file.fixes()[0].record.fix_accuracy(), None);
+            assert_eq!(file.warnings()[0].kind, ErrorKind::AdditionOutOfRange);
+        }
+    }
+}
@@ -0,0 +1,382 @@
+//! Distances, bearings and destination points on the FAI sphere or on the
+//! WGS84 ellipsoid
+//!
+//! All distances are in meters and all angles in degrees. Bearings are
+//! measured clockwise from true north and are between 0 and 360.
+
+use ::{BRecord, BRecordRef, CRecordTurnpoint, Timestamped};
+
+/// Radius of the FAI sphere in meters
+pub const FAI_EARTH_RADIUS: f64 = 6_371_000.;
+
+/// Semi-major axis of the WGS84 ellipsoid in meters
+pub const WGS84_A: f64 = 6_378_137.;
+
+/// Flattening of the WGS84 ellipsoid
+pub const WGS84_F: f64 = 1. / 298.257_223_563;
+
+/// Iterations after which Vincenty's formulae are considered to not converge
+const MAX_ITERATIONS: usize = 200;
+
+/// Point on the surface of the earth
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Point {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Point {
+    pub fn new(latitude: f64, longitude: f64) -> Point {
+        Point { latitude, longitude }
+    }
+}
+
+/// Anything with a latitude and longitude in degrees
+pub trait Position {
+    fn point(&self) -> Point;
+}
+
+impl Position for Point {
+    fn point(&self) -> Point {
+        *self
+    }
+}
+
+/// Uses the `LAD` and `LOD` additions, if the fix has them.
+impl Position for BRecord {
+    fn point(&self) -> Point {
+        Point::new(self.latitude(), self.longitude())
+    }
+}
+
+/// Uses the `LAD` and `LOD` additions, if the fix has them.
+impl<'a> Position for BRecordRef<'a> {
+    fn point(&self) -> Point {
+        Point::new(self.latitude(), self.longitude())
+    }
+}
+
+impl Position for CRecordTurnpoint {
+    fn point(&self) -> Point {
+        Point::new(self.latitude, self.longitude)
+    }
+}
+
+impl<T: Position> Position for Timestamped<T> {
+    fn point(&self) -> Point {
+        self.record.point()
+    }
+}
+
+impl<T: Position + ?Sized> Position for &T {
+    fn point(&self) -> Point {
+        (**self).point()
+    }
+}
+
+/// Shape of the earth that the calculations are based on
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Model {
+    /// Sphere with the radius of 6371 km that the FAI Sporting Code defines
+    #[default]
+    FaiSphere,
+
+    /// WGS84 ellipsoid, using Vincenty's formulae
+    ///
+    /// For nearly antipodal points, the inverse formula does not converge,
+    /// so there is no distance or bearing between them.
+    Wgs84,
+}
+
+impl Model {
+    /// Shortest distance between two points, or `None` if it can't be
+    /// calculated, see `Model::Wgs84`
+    pub fn distance<A: Position, B: Position>(&self, from: A, to: B) -> Option<f64> {
+        self.inverse(from.point(), to.point()).map(|inverse| inverse.distance)
+    }
+
+    /// Bearing at `from` of the shortest path to `to`, or `None` if it can't
+    /// be calculated, see `Model::Wgs84`
+    pub fn initial_bearing<A: Position, B: Position>(&self, from: A, to: B) -> Option<f64> {
+        self.inverse(from.point(), to.point()).map(|inverse| inverse.initial_bearing)
+    }
+
+    /// Bearing at `to` of the shortest path from `from`, or `None` if it
+    /// can't be calculated, see `Model::Wgs84`
+    pub fn final_bearing<A: Position, B: Position>(&self, from: A, to: B) -> Option<f64> {
+        self.inverse(from.point(), to.point()).map(|inverse| inverse.final_bearing)
+    }
+
+    /// Point that is reached when travelling the `distance` from `from` with
+    /// the given initial `bearing`
+    pub fn destination<A: Position>(&self, from: A, bearing: f64, distance: f64) -> Point {
+        match *self {
+            Model::FaiSphere => sphere_destination(from.point(), bearing, distance),
+            Model::Wgs84 => vincenty_destination(from.point(), bearing, distance),
+        }
+    }
+
+    fn inverse(&self, from: Point, to: Point) -> Option<Inverse> {
+        match *self {
+            Model::FaiSphere => Some(sphere_inverse(from, to)),
+            Model::Wgs84 => vincenty_inverse(from, to),
+        }
+    }
+}
+
+/// Solution of the inverse geodesic problem
+struct Inverse {
+    distance: f64,
+    initial_bearing: f64,
+    final_bearing: f64,
+}
+
+fn sphere_inverse(from: Point, to: Point) -> Inverse {
+    let (lat1, lat2) = (from.latitude.to_radians(), to.latitude.to_radians());
+    let delta_lat = lat2 - lat1;
+    let delta_lon = (to.longitude - from.longitude).to_radians();
+
+    let a = (delta_lat / 2.).sin().powi(2) + lat1.cos() * lat2.cos() * (delta_lon / 2.).sin().powi(2);
+    let distance = 2. * FAI_EARTH_RADIUS * a.sqrt().atan2((1. - a).sqrt());
+
+    Inverse {
+        distance,
+        initial_bearing: sphere_bearing(lat1, lat2, delta_lon),
+        final_bearing: normalize_bearing(sphere_bearing(lat2, lat1, -delta_lon) + 180.),
+    }
+}
+
+fn sphere_bearing(lat1: f64, lat2: f64, delta_lon: f64) -> f64 {
+    let y = delta_lon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * delta_lon.cos();
+    normalize_bearing(y.atan2(x).to_degrees())
+}
+
+fn sphere_destination(from: Point, bearing: f64, distance: f64) -> Point {
+    let lat1 = from.latitude.to_radians();
+    let bearing = bearing.to_radians();
+    let delta = distance / FAI_EARTH_RADIUS;
+
+    let lat2 = (lat1.sin() * delta.cos() + lat1.cos() * delta.sin() * bearing.cos()).asin();
+    let delta_lon = (bearing.sin() * delta.sin() * lat1.cos()).atan2(delta.cos() - lat1.sin() * lat2.sin());
+
+    Point::new(lat2.to_degrees(), normalize_longitude(from.longitude + delta_lon.to_degrees()))
+}
+
+/// Vincenty's inverse formula, or `None` if it does not converge
+fn vincenty_inverse(from: Point, to: Point) -> Option<Inverse> {
+    let b = WGS84_A * (1. - WGS84_F);
+    let l = (to.longitude - from.longitude).to_radians();
+    let u1 = ((1. - WGS84_F) * from.latitude.to_radians().tan()).atan();
+    let u2 = ((1. - WGS84_F) * to.latitude.to_radians().tan()).atan();
+    let (sin_u1, cos_u1) = u1.sin_cos();
+    let (sin_u2, cos_u2) = u2.sin_cos();
+
+    let mut lambda = l;
+    for _ in 0..MAX_ITERATIONS {
+        let (sin_lambda, cos_lambda) = lambda.sin_cos();
+        let sin_sigma = ((cos_u2 * sin_lambda).powi(2)
+            + (cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda).powi(2)).sqrt();
+        if sin_sigma == 0. {
+            // coincident points
+            return Some(Inverse { distance: 0., initial_bearing: 0., final_bearing: 0. });
+        }
+
+        let cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_lambda;
+        let sigma = sin_sigma.atan2(cos_sigma);
+        let sin_alpha = cos_u1 * cos_u2 * sin_lambda / sin_sigma;
+        let cos_sq_alpha = 1. - sin_alpha * sin_alpha;
+        // on the equator, `cos_sq_alpha` is zero
+        let cos_2sigma_m = if cos_sq_alpha == 0. { 0. } else { cos_sigma - 2. * sin_u1 * sin_u2 / cos_sq_alpha };
+        let c = WGS84_F / 16. * cos_sq_alpha * (4. + WGS84_F * (4. - 3. * cos_sq_alpha));
+
+        let previous = lambda;
+        lambda = l + (1. - c) * WGS84_F * sin_alpha
+            * (sigma + c * sin_sigma * (cos_2sigma_m + c * cos_sigma * (-1. + 2. * cos_2sigma_m * cos_2sigma_m)));
+
+        if (lambda - previous).abs() < 1e-12 {
+            let u_sq = cos_sq_alpha * (WGS84_A * WGS84_A - b * b) / (b * b);
+            let (big_a, big_b) = series(u_sq);
+            let delta_sigma = big_b * sin_sigma * (cos_2sigma_m + big_b / 4.
+                * (cos_sigma * (-1. + 2. * cos_2sigma_m * cos_2sigma_m)
+                    - big_b / 6. * cos_2sigma_m * (-3. + 4. * sin_sigma * sin_sigma) * (-3. + 4. * cos_2sigma_m * cos_2sigma_m)));
+
+            let (sin_lambda, cos_lambda) = lambda.sin_cos();
+            let alpha1 = (cos_u2 * sin_lambda).atan2(cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda);
+            let alpha2 = (cos_u1 * sin_lambda).atan2(-sin_u1 * cos_u2 + cos_u1 * sin_u2 * cos_lambda);
+
+            return Some(Inverse {
+                distance: b * big_a * (sigma - delta_sigma),
+                initial_bearing: normalize_bearing(alpha1.to_degrees()),
+                final_bearing: normalize_bearing(alpha2.to_degrees()),
+            });
+        }
+    }
+
+    None
+}
+
+/// Vincenty's direct formula
+fn vincenty_destination(from: Point, bearing: f64, distance: f64) -> Point {
+    let b = WGS84_A * (1. - WGS84_F);
+    let (sin_alpha1, cos_alpha1) = bearing.to_radians().sin_cos();
+
+    let tan_u1 = (1. - WGS84_F) * from.latitude.to_radians().tan();
+    let cos_u1 = 1. / (1. + tan_u1 * tan_u1).sqrt();
+    let sin_u1 = tan_u1 * cos_u1;
+    let sigma1 = tan_u1.atan2(cos_alpha1);
+    let sin_alpha = cos_u1 * sin_alpha1;
+    let cos_sq_alpha = 1. - sin_alpha * sin_alpha;
+    let u_sq = cos_sq_alpha * (WGS84_A * WGS84_A - b * b) / (b * b);
+    let (big_a, big_b) = series(u_sq);
+
+    let mut sigma = distance / (b * big_a);
+    let mut cos_2sigma_m = (2. * sigma1 + sigma).cos();
+    for _ in 0..MAX_ITERATIONS {
+        cos_2sigma_m = (2. * sigma1 + sigma).cos();
+        let (sin_sigma, cos_sigma) = sigma.sin_cos();
+        let delta_sigma = big_b * sin_sigma * (cos_2sigma_m + big_b / 4.
+            * (cos_sigma * (-1. + 2. * cos_2sigma_m * cos_2sigma_m)
+                - big_b / 6. * cos_2sigma_m * (-3. + 4. * sin_sigma * sin_sigma) * (-3. + 4. * cos_2sigma_m * cos_2sigma_m)));
+
+        let previous = sigma;
+        sigma = distance / (b * big_a) + delta_sigma;
+        if (sigma - previous).abs() < 1e-12 {
+            break;
+        }
+    }
+
+    let (sin_sigma, cos_sigma) = sigma.sin_cos();
+    let x = sin_u1 * sin_sigma - cos_u1 * cos_sigma * cos_alpha1;
+    let lat2 = (sin_u1 * cos_sigma + cos_u1 * sin_sigma * cos_alpha1)
+        .atan2((1. - WGS84_F) * (sin_alpha * sin_alpha + x * x).sqrt());
+    let lambda = (sin_sigma * sin_alpha1).atan2(cos_u1 * cos_sigma - sin_u1 * sin_sigma * cos_alpha1);
+    let c = WGS84_F / 16. * cos_sq_alpha * (4. + WGS84_F * (4. - 3. * cos_sq_alpha));
+    let l = lambda - (1. - c) * WGS84_F * sin_alpha
+        * (sigma + c * sin_sigma * (cos_2sigma_m + c * cos_sigma * (-1. + 2. * cos_2sigma_m * cos_2sigma_m)));
+
+    Point::new(lat2.to_degrees(), normalize_longitude(from.longitude + l.to_degrees()))
+}
+
+/// Coefficients `A` and `B` of Vincenty's formulae
+fn series(u_sq: f64) -> (f64, f64) {
+    let a = 1. + u_sq / 16384. * (4096. + u_sq * (-768. + u_sq * (320. - 175. * u_sq)));
+    let b = u_sq / 1024. * (256. + u_sq * (-128. + u_sq * (74. - 47. * u_sq)));
+    (a, b)
+}
+
+fn normalize_bearing(degrees: f64) -> f64 {
+    let degrees = degrees % 360.;
+    if degrees < 0. { degrees + 360. } else { degrees }
+}
+
+fn normalize_longitude(degrees: f64) -> f64 {
+    let degrees = (degrees + 180.) % 360.;
+    if degrees < 0. { degrees + 180. } else { degrees - 180. }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use ::{IgcFile, Record};
+
+    fn dms(degrees: f64, minutes: f64, seconds: f64) -> f64 {
+        degrees + minutes / 60. + seconds / 3600.
+    }
+
+    /// Example of Vincenty's paper, from Flinders Peak to Buninyong
+    fn flinders_peak() -> Point {
+        Point::new(-dms(37., 57., 3.72030), dms(144., 25., 29.52440))
+    }
+
+    fn buninyong() -> Point {
+        Point::new(-dms(37., 39., 10.15610), dms(143., 55., 35.38390))
+    }
+
+    #[test]
+    fn test_sphere() {
+        let model = Model::FaiSphere;
+        let (a, b) = (Point::new(0., 0.), Point::new(0., 1.));
+        assert_relative_eq!(model.distance(a, b).unwrap(), FAI_EARTH_RADIUS * PI / 180.);
+        assert_relative_eq!(model.initial_bearing(a, b).unwrap(), 90.);
+        assert_relative_eq!(model.final_bearing(a, b).unwrap(), 90.);
+        assert_eq!(model.distance(a, a).unwrap(), 0.);
+
+        let (a, b) = (Point::new(50., -5.), Point::new(58., 3.));
+        assert_relative_eq!(model.distance(a, b).unwrap(), 1_030_265.55, epsilon = 0.01);
+        assert_relative_eq!(model.initial_bearing(a, b).unwrap(), 27.261_69, epsilon = 1e-5);
+        assert_relative_eq!(model.final_bearing(a, b).unwrap(), 33.753_24, epsilon = 1e-5);
+
+        let destination = model.destination(a, model.initial_bearing(a, b).unwrap(), model.distance(a, b).unwrap());
+        assert_relative_eq!(destination.latitude, b.latitude, epsilon = 1e-9);
+        assert_relative_eq!(destination.longitude, b.longitude, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_wgs84() {
+        let model = Model::Wgs84;
+        let (a, b) = (flinders_peak(), buninyong());
+        assert_relative_eq!(model.distance(a, b).unwrap(), 54_972.271, epsilon = 1e-3);
+        assert_relative_eq!(model.initial_bearing(a, b).unwrap(), dms(306., 52., 5.37), epsilon = 1e-5);
+        assert_relative_eq!(model.final_bearing(a, b).unwrap(), dms(127., 10., 25.07) + 180., epsilon = 1e-5);
+
+        let destination = model.destination(a, dms(306., 52., 5.37), 54_972.271);
+        assert_relative_eq!(destination.latitude, b.latitude, epsilon = 1e-7);
+        assert_relative_eq!(destination.longitude, b.longitude, epsilon = 1e-7);
+
+        let (a, b) = (Point::new(0., 0.), Point::new(0., 1.));
+        assert_relative_eq!(model.distance(a, b).unwrap(), WGS84_A * PI / 180., epsilon = 1e-6);
+        assert_relative_eq!(model.initial_bearing(a, b).unwrap(), 90.);
+        assert_eq!(model.distance(a, a).unwrap(), 0.);
+    }
+
+    #[test]
+    fn test_antipodal() {
+        let (a, b) = (Point::new(0., 0.), Point::new(0.5, 179.7));
+        assert!(Model::FaiSphere.distance(a, b).is_some());
+        assert_eq!(Model::Wgs84.distance(a, b), None);
+        assert_eq!(Model::Wgs84.initial_bearing(a, b), None);
+        assert_eq!(Model::Wgs84.final_bearing(a, b), None);
+
+        // not quite antipodal
+        assert!(Model::Wgs84.distance(a, Point::new(0.5, 179.)).is_some());
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize_bearing(-90.), 270.);
+        assert_eq!(normalize_bearing(360.), 0.);
+        assert_eq!(normalize_longitude(181.), -179.);
+        assert_eq!(normalize_longitude(-181.), 179.);
+        assert_eq!(normalize_longitude(10.), 10.);
+    }
+
+    #[test]
+    fn test_records() {
+        let file = IgcFile::parse_bytes(b"AFLA6NG\r
+HFDTE040516\r
+C040516072924000000000001Task\r
+C5049383N00611183ETakeoff\r
+C5049383N00611183EStart\r
+C5110000N00630000ETP\r
+C5049383N00611183EFinish\r
+C5049383N00611183ELanding\r
+B0810425049383N00611183EA000480023000208\r
+").unwrap();
+
+        let task = file.task().unwrap();
+        let fix = &file.fixes()[0];
+        assert_eq!(Model::FaiSphere.distance(fix, task.start().unwrap()), Some(0.));
+        assert_relative_eq!(Model::FaiSphere.distance(fix, &task.turnpoints()[0]).unwrap(), 44_063.71, epsilon = 0.01);
+        assert!(Model::Wgs84.distance(fix, &task.turnpoints()[0]).unwrap() > Model::FaiSphere.distance(fix, &task.turnpoints()[0]).unwrap());
+
+        match Record::parse(b"B0810425049383N00611183EA000480023000208").unwrap() {
+            Record::B(ref record) => assert_eq!(record.point(), fix.point()),
+            ref record => panic!("unexpected record: {:?}", record),
+        }
+    }
+}
@@ -1,23 +1,107 @@
-extern crate encoding;
+// record and code names follow the three-letter codes of the IGC specification
+#![allow(clippy::upper_case_acronyms)]
+// without the `std` feature only the record parsers are available, which
+// just need `core` and `alloc`. The tests still link `std` for the test
+// harness and `proptest`.
+#![cfg_attr(all(not(feature = "std"), not(test)), no_std)]
 
-#[macro_use] extern crate failure;
-#[macro_use] extern crate lazy_static;
-extern crate regex;
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+// the `strum_macros` derives use `::core` paths
+#[cfg(any(feature = "std", test))]
+extern crate core;
+
+#[cfg(feature = "std")]
+extern crate failure;
+#[cfg(feature = "compression")]
+extern crate flate2;
+#[cfg(feature = "tokio")]
+extern crate futures_core;
+#[cfg(feature = "parallel")]
+extern crate rayon;
 extern crate strum;
 #[macro_use] extern crate strum_macros;
+#[cfg(feature = "tokio")]
+extern crate tokio;
+#[cfg(feature = "compression")]
+extern crate zip;
+#[cfg(feature = "mmap")]
+extern crate memmap2;
 
 #[cfg(test)]
 #[macro_use]
 extern crate approx;
 
+#[cfg(test)]
+#[macro_use]
+extern crate lazy_static;
+
 #[cfg(test)]
 #[macro_use]
 extern crate proptest;
 
+#[cfg(test)]
+extern crate regex;
+
+/// The parts of `std` that are also available in `core` and `alloc`, so that
+/// the rest of the crate can use `std` paths with and without the `std`
+/// feature
+#[cfg(all(not(feature = "std"), not(test)))]
+mod std {
+    pub use core::*;
+    pub use alloc::{borrow, fmt, str, string, vec};
+
+    pub mod collections {
+        pub use alloc::collections::*;
+    }
+}
+
+/// Types of the `std` prelude that are not in the `core` prelude
+mod prelude {
+    pub use std::borrow::ToOwned;
+    pub use std::string::{String, ToString};
+    pub use std::vec::Vec;
+}
+
 mod records;
 mod error;
+#[cfg(feature = "tokio")]
+mod async_reader;
+#[cfg(feature = "std")]
+mod file;
+#[cfg(feature = "std")]
+pub mod geo;
+#[cfg(feature = "std")]
+mod reader;
+#[cfg(feature = "std")]
+mod security;
+mod task;
+mod validation;
+#[cfg(feature = "std")]
+mod writer;
 pub mod utils;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+#[cfg(feature = "compression")]
+pub mod compression;
+#[cfg(feature = "mmap")]
+pub mod mmap;
 
 pub use self::records::*;
-pub use self::error::{Error, Result};
-pub use self::utils::datetime::{Time, Date};
+pub use self::error::{Error, ErrorKind, ParseError, Result};
+#[cfg(feature = "tokio")]
+pub use self::async_reader::AsyncReader;
+#[cfg(feature = "std")]
+pub use self::file::{IgcFile, Timestamped};
+#[cfg(feature = "std")]
+pub use self::reader::{Reader, SliceReader, ParsedLine, ParserState, RawLine};
+#[cfg(feature = "std")]
+pub use self::security::{SecurityRegistry, SecurityValidator, Signer, SignedContent, Verification, XcsoarSigner,
+                         XcsoarValidator};
+pub use self::task::Task;
+pub use self::validation::{ParseMode, ParseOptions, validate};
+#[cfg(feature = "std")]
+pub use self::writer::Writer;
+pub use self::utils::datetime::{Time, Date, DateTime};
+pub use self::utils::text::Encoding;
@@ -0,0 +1,132 @@
+//! Parsing of memory-mapped IGC files
+//!
+//! The lines are parsed directly from the mapped bytes, so large files are
+//! never copied into heap buffers. Together with `BRecordRef` and
+//! `KRecordRef`, the fixes of a file can be processed without any
+//! allocations per line.
+//!
+//! Opening a file is `unsafe`, since the mapped bytes change if the file is
+//! modified while it is mapped.
+//!
+//! This module is only available with the `mmap` feature enabled.
+
+use std::fs::File;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use ::{IgcFile, ParseOptions, Result, SliceReader};
+use ::utils::lines::SliceLines;
+
+/// IGC file that is mapped into memory
+#[derive(Debug)]
+pub struct MappedFile {
+    map: Mmap,
+}
+
+impl MappedFile {
+    /// Maps the file at `path` into memory.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated, by this or any other
+    /// process, while the `MappedFile` exists, since the bytes that it
+    /// borrows out would then change or become inaccessible.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> Result<MappedFile> {
+        let file = File::open(path)?;
+        // SAFETY: the caller guarantees that the file is not changed while
+        // it is mapped
+        let map = Mmap::map(&file)?;
+        Ok(MappedFile { map })
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Lines of the file, split in the same way as `ByteLines` does
+    pub fn lines(&self) -> SliceLines<'_> {
+        SliceLines::new(&self.map)
+    }
+
+    /// Reader that parses the records of the file one line at a time
+    pub fn reader(&self, options: ParseOptions) -> SliceReader<'_> {
+        SliceReader::with_options(&self.map, options)
+    }
+
+    /// Parses all records of the file, see `IgcFile::parse_with_options()`.
+    pub fn parse(&self, options: ParseOptions) -> Result<IgcFile> {
+        IgcFile::parse_bytes_with_options(&self.map, options)
+    }
+}
+
+/// Maps the file at `path` into memory and parses all of its records.
+///
+/// # Safety
+///
+/// The file must not be modified or truncated while it is parsed, see
+/// `MappedFile::open()`.
+pub unsafe fn parse_path<P: AsRef<Path>>(path: P, options: ParseOptions) -> Result<IgcFile> {
+    MappedFile::open(path)?.parse(options)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    use super::*;
+    use ::{BRecordRef, ParserState, Reader};
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(name)
+    }
+
+    #[test]
+    fn test_same_as_reader() {
+        for entry in fs::read_dir(fixture("")).unwrap() {
+            let path = entry.unwrap().path();
+            let bytes = fs::read(&path).unwrap();
+            // SAFETY: the fixtures are not modified by the tests
+            let file = unsafe { MappedFile::open(&path).unwrap() };
+            assert_eq!(file.bytes(), &bytes[..]);
+
+            for options in &[ParseOptions::default(), ParseOptions::lenient(), ParseOptions::strict()] {
+                let mut expected = Reader::with_options(Cursor::new(&bytes), *options).keep_raw_lines(true);
+                let mut reader = file.reader(*options).keep_raw_lines(true);
+                for line in &mut reader {
+                    match (line, expected.next().unwrap()) {
+                        (Ok(line), Ok(expected)) => assert_eq!(line, expected),
+                        (Err(error), Err(expected)) => assert_eq!(error.to_string(), expected.to_string()),
+                        (line, expected) => panic!("{:?} != {:?}", line, expected),
+                    }
+                }
+                assert!(expected.next().is_none());
+                assert_eq!(reader.warnings().len(), expected.warnings().len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_path() {
+        // SAFETY: the fixtures are not modified by the tests
+        let file = unsafe { parse_path(fixture("654g6ng1.igc"), ParseOptions::default()).unwrap() };
+        assert_eq!(file.fixes().len(), 9762);
+
+        assert!(unsafe { parse_path(fixture("missing.igc"), ParseOptions::default()) }.is_err());
+    }
+
+    #[test]
+    fn test_borrowed_fixes() {
+        // SAFETY: the fixtures are not modified by the tests
+        let file = unsafe { MappedFile::open(fixture("654g6ng1.igc")).unwrap() };
+        let fixes: Vec<_> = file.lines()
+            .filter(|&(line, _)| line.first() == Some(&b'B'))
+            .map(|(line, _)| BRecordRef::parse(line).unwrap())
+            .collect();
+        assert_eq!(fixes.len(), 9762);
+        assert_eq!(fixes[0].time, file.parse(ParseOptions::default()).unwrap().fixes()[0].time);
+    }
+}
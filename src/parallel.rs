@@ -0,0 +1,349 @@
+//! Parsing of many files, or of a single large file, on all CPU cores
+//!
+//! This module is only available with the `parallel` feature enabled.
+
+use std::cmp::max;
+use std::fs::{self, File};
+use std::io::{BufReader, Cursor};
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+
+use ::{Error, ParseError, ParseOptions, ParserState, Result, Reader, ParsedLine, Record, DateTime, Time};
+use ::utils::datetime::DateTracker;
+use ::utils::headers::{HeaderCode, HeaderValue};
+use ::utils::lines::SliceLines;
+
+/// Smallest chunk that `parse_bytes()` hands to a single thread
+const MIN_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Result of parsing a single file with `parse_files()` or `parse_dir()`
+#[derive(Debug)]
+pub struct FileSummary {
+    pub path: PathBuf,
+
+    /// Number of B records that could be parsed
+    pub fixes: usize,
+
+    /// Date and time of the first fix, if the date of the file is known
+    pub first_fix: Option<DateTime>,
+
+    /// Date and time of the last fix, if the date of the file is known
+    pub last_fix: Option<DateTime>,
+
+    /// Lines that could not be parsed, or the I/O error that stopped reading
+    /// the file
+    pub errors: Vec<Error>,
+
+    /// Problems that were found in lenient mode
+    pub warnings: Vec<ParseError>,
+}
+
+impl FileSummary {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Parses all `.igc` files in a directory (not recursively), sorted by path.
+pub fn parse_dir<P: AsRef<Path>>(dir: P, options: ParseOptions) -> Result<Vec<FileSummary>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("igc")) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    Ok(parse_files(&paths, options))
+}
+
+/// Parses the files in parallel and returns a summary for each of them, in
+/// the same order as `paths`.
+pub fn parse_files<P: AsRef<Path> + Sync>(paths: &[P], options: ParseOptions) -> Vec<FileSummary> {
+    paths.par_iter().map(|path| summarize(path.as_ref(), options)).collect()
+}
+
+fn summarize(path: &Path, options: ParseOptions) -> FileSummary {
+    let mut summary = FileSummary {
+        path: path.to_owned(),
+        fixes: 0,
+        first_fix: None,
+        last_fix: None,
+        errors: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) => {
+            summary.errors.push(error.into());
+            return summary;
+        },
+    };
+
+    let mut reader = Reader::with_options(BufReader::new(file), options);
+    for line in &mut reader {
+        match line {
+            Ok(ParsedLine { record: Some(Record::B(_)), datetime, .. }) => {
+                summary.fixes += 1;
+                if datetime.is_some() {
+                    summary.first_fix = summary.first_fix.or(datetime);
+                    summary.last_fix = datetime;
+                }
+            },
+            Ok(_) => {},
+            Err(error @ Error::Io(_)) => {
+                summary.errors.push(error);
+                break;
+            },
+            Err(error) => summary.errors.push(error),
+        }
+    }
+
+    summary.warnings = reader.take_warnings();
+    summary
+}
+
+/// All lines of a file that was parsed with `parse_bytes()`
+#[derive(Debug)]
+pub struct ParsedBytes {
+    /// The same lines and errors that a `Reader` would return
+    pub lines: Vec<Result<ParsedLine>>,
+
+    /// Problems that were found in lenient mode
+    pub warnings: Vec<ParseError>,
+}
+
+/// Parses an IGC file that is already in memory by splitting it into chunks
+/// on line boundaries and parsing the chunks in parallel.
+///
+/// The result is the same as reading the file with a `Reader`. Before the
+/// chunks are parsed, only the I, J and `DTE` header lines are parsed to find
+/// the addition definitions and the date at the start of each chunk, and
+/// midnight rollovers at the chunk boundaries are fixed up afterwards.
+pub fn parse_bytes(bytes: &[u8], options: ParseOptions) -> ParsedBytes {
+    let chunk_size = max(bytes.len() / (rayon::current_num_threads() * 4), MIN_CHUNK_SIZE);
+    parse_chunks(bytes, options, chunk_size)
+}
+
+fn parse_chunks(bytes: &[u8], options: ParseOptions, chunk_size: usize) -> ParsedBytes {
+    let mut context = Reader::with_options(Cursor::new(&[][..]), options);
+    let mut readers = Vec::new();
+    for chunk in split_lines(bytes, chunk_size) {
+        readers.push(Reader::continue_from(Cursor::new(chunk), &context));
+
+        for (line, _) in SliceLines::new(chunk) {
+            context.skip_line(line);
+        }
+    }
+
+    let mut chunks: Vec<_> = readers.into_par_iter()
+        .map(|mut reader| {
+            let lines: Vec<_> = (&mut reader).collect();
+            (lines, reader.take_warnings())
+        })
+        .collect();
+
+    fix_rollovers(&mut chunks);
+
+    let mut parsed = ParsedBytes { lines: Vec::new(), warnings: Vec::new() };
+    for (lines, warnings) in chunks {
+        parsed.lines.extend(lines);
+        parsed.warnings.extend(warnings);
+    }
+    parsed
+}
+
+/// Splits the bytes after the first line feed following every `chunk_size`
+/// bytes.
+fn split_lines(bytes: &[u8], chunk_size: usize) -> Vec<&[u8]> {
+    let mut chunks = Vec::new();
+    let mut rest = bytes;
+    while rest.len() > chunk_size {
+        match rest[chunk_size..].iter().position(|b| *b == b'\n') {
+            Some(end) => {
+                let (chunk, tail) = rest.split_at(chunk_size + end + 1);
+                chunks.push(chunk);
+                rest = tail;
+            },
+            None => break,
+        }
+    }
+
+    if !rest.is_empty() {
+        chunks.push(rest);
+    }
+    chunks
+}
+
+/// Moves the dates of the records in each chunk forward by the number of
+/// midnight rollovers that happened since the last `DTE` header in the
+/// previous chunks, which the reader of the chunk could not know about.
+fn fix_rollovers(chunks: &mut [(Vec<Result<ParsedLine>>, Vec<ParseError>)]) {
+    let mut rollovers = 0;
+    let mut last_time: Option<Time> = None;
+
+    for &mut (ref mut lines, _) in chunks {
+        // after a `DTE` header, the reader of the chunk tracks the date itself
+        let mut days = Some(rollovers);
+        let mut is_first = true;
+
+        for line in lines.iter_mut().filter_map(|line| line.as_mut().ok()) {
+            let record = match line.record {
+                Some(ref record) => record,
+                None => continue,
+            };
+
+            if is_date_header(record) {
+                rollovers = 0;
+                last_time = None;
+                days = None;
+                continue;
+            }
+
+            let time = match record_time(record) {
+                Some(time) => time,
+                None => continue,
+            };
+
+            if last_time.is_some_and(|last_time| DateTracker::is_rollover(last_time, time)) {
+                rollovers += 1;
+                // the reader of the chunk only misses a rollover at its start
+                if is_first {
+                    days = days.map(|days| days + 1);
+                }
+            }
+            is_first = false;
+            last_time = Some(time);
+
+            if let Some(days) = days {
+                line.datetime = line.datetime.map(|datetime| add_days(datetime, days));
+            }
+        }
+    }
+}
+
+/// Same check as in `Reader`, which resets its date for these headers
+fn is_date_header(record: &Record) -> bool {
+    match *record {
+        Record::H(ref record) if record.code == HeaderCode::DTE => {
+            matches!(record.value(), HeaderValue::Date { .. })
+        },
+        _ => false,
+    }
+}
+
+/// Time of the records that the `Reader` calculates a `datetime` for
+fn record_time(record: &Record) -> Option<Time> {
+    match *record {
+        Record::B(ref record) => Some(record.time),
+        Record::E(ref record) => Some(record.time),
+        Record::F(ref record) => Some(record.time),
+        Record::K(ref record) => Some(record.time),
+        _ => None,
+    }
+}
+
+fn add_days(mut datetime: DateTime, days: usize) -> DateTime {
+    for _ in 0..days {
+        datetime.date = datetime.date.next_day();
+    }
+    datetime
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::{Date, ErrorKind};
+
+    fn fixtures() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures")
+    }
+
+    fn read_all(bytes: &[u8], options: ParseOptions) -> ParsedBytes {
+        let mut reader = Reader::with_options(Cursor::new(bytes), options);
+        let lines: Vec<_> = (&mut reader).collect();
+        ParsedBytes { lines, warnings: reader.take_warnings() }
+    }
+
+    fn assert_same(parsed: &ParsedBytes, expected: &ParsedBytes) {
+        assert_eq!(parsed.lines.len(), expected.lines.len());
+        for (line, expected) in parsed.lines.iter().zip(&expected.lines) {
+            match (line, expected) {
+                (Ok(line), Ok(expected)) => assert_eq!(line, expected),
+                (Err(error), Err(expected)) => assert_eq!(error.to_string(), expected.to_string()),
+                _ => panic!("{:?} != {:?}", line, expected),
+            }
+        }
+
+        let warnings: Vec<_> = parsed.warnings.iter().map(ToString::to_string).collect();
+        let expected: Vec<_> = expected.warnings.iter().map(ToString::to_string).collect();
+        assert_eq!(warnings, expected);
+    }
+
+    #[test]
+    fn test_split_lines() {
+        assert_eq!(split_lines(b"", 2), Vec::<&[u8]>::new());
+        assert_eq!(split_lines(b"ab\ncd\nef", 1), vec![&b"ab\n"[..], b"cd\n", b"ef"]);
+        assert_eq!(split_lines(b"ab\ncd\nef\n", 4), vec![&b"ab\ncd\n"[..], b"ef\n"]);
+        assert_eq!(split_lines(b"ab\ncd\nef", 100), vec![&b"ab\ncd\nef"[..]]);
+    }
+
+    #[test]
+    fn test_same_as_reader() {
+        for entry in fs::read_dir(fixtures()).unwrap() {
+            let bytes = fs::read(entry.unwrap().path()).unwrap();
+            for options in &[ParseOptions::default(), ParseOptions::lenient(), ParseOptions::strict()] {
+                let expected = read_all(&bytes, *options);
+                for chunk_size in &[1, 1000, MIN_CHUNK_SIZE] {
+                    assert_same(&parse_chunks(&bytes, *options, *chunk_size), &expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_midnight_rollover() {
+        let bytes = b"AXXX001\nHFDTE311215\nB2359585016925N00953112EA0216402287\n\
+            I013636TDS\nB0000025016925N00953112EA02164022875\nB0100025016925N00953112EA02164022875\n\
+            HFDTE010616\nB2359595016925N00953112EA02164022875\nE000000PEV\n";
+
+        let expected = read_all(bytes, ParseOptions::default());
+        let dates: Vec<_> = expected.lines.iter()
+            .filter_map(|line| line.as_ref().unwrap().datetime)
+            .map(|datetime| datetime.date)
+            .collect();
+        assert_eq!(dates, vec![
+            Date::new(15, 12, 31),
+            Date::new(16, 1, 1),
+            Date::new(16, 1, 1),
+            Date::new(16, 6, 1),
+            Date::new(16, 6, 2),
+        ]);
+
+        for chunk_size in 1..bytes.len() {
+            assert_same(&parse_chunks(bytes, ParseOptions::default(), chunk_size), &expected);
+        }
+    }
+
+    #[test]
+    fn test_parse_dir() {
+        let summaries = parse_dir(fixtures(), ParseOptions::default()).unwrap();
+        let names: Vec<_> = summaries.iter().map(|summary| summary.path.file_name().unwrap().to_str().unwrap()).collect();
+        assert_eq!(names, vec!["05fd5hv1.igc", "20090715.igc", "2015-05-14-xlk-sem-01.igc", "654g6ng1.igc", "87el0ep1.igc"]);
+
+        let summary = &summaries[1];
+        assert_eq!(summary.fixes, 3076);
+        assert_eq!(summary.errors.len(), 2);
+        assert_eq!(summary.errors[0].kind(), Some(ErrorKind::BadCoordinate));
+        assert!(summary.first_fix.unwrap() < summary.last_fix.unwrap());
+        assert!(summaries[3].is_ok());
+
+        let summaries = parse_files(&[fixtures().join("missing.igc")], ParseOptions::default());
+        assert_eq!(summaries[0].errors.len(), 1);
+        assert_eq!(summaries[0].errors[0].kind(), None);
+    }
+}
@@ -0,0 +1,625 @@
+use std::io::BufRead;
+
+use ::{Error, ErrorKind, ParseError, ParseMode, ParseOptions, Result, Record, BRecord, KRecord, Date, DateTime};
+use ::validation::validate;
+use ::utils::additions::{AdditionDef, AdditionSupport};
+use ::utils::datetime::DateTracker;
+use ::utils::headers::{HeaderCode, HeaderValue};
+use ::utils::lines::{ByteLines, LineEnding, SliceLines};
+use ::utils::text::Encoding;
+
+/// Record parsed from a single line of an IGC file
+#[derive(Debug, PartialEq)]
+pub struct ParsedLine {
+    /// Line number in the file (1-indexed!)
+    pub line_number: usize,
+
+    /// Record of the line, or `None` for the lines without a record that are
+    /// only returned with `keep_raw_lines()` enabled
+    pub record: Option<Record>,
+
+    /// Full UTC date and time of B, E, F and K records, based on the `DTE`
+    /// header and the `TDS` addition, if available
+    pub datetime: Option<DateTime>,
+
+    /// Original bytes of the line, if the `Reader` was told to keep them
+    pub raw: Option<RawLine>,
+}
+
+/// Original bytes of a line, without the line ending
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RawLine {
+    pub bytes: Vec<u8>,
+    pub line_ending: LineEnding,
+}
+
+/// Reads all records of an IGC file and keeps track of the I and J record
+/// addition definitions, so that the B and K records are decoded with all
+/// of their additions.
+///
+/// The times of the B, E, F and K records are combined with the date of the
+/// `DTE` header, taking flights across midnight UTC into account.
+///
+/// Empty lines are skipped, but still counted for the line numbers.
+///
+/// The `ParseOptions` decide if the lines are validated against the
+/// specification. In lenient mode the reader does not yield any parse
+/// errors, but collects them as `warnings()` instead.
+///
+/// Unless the `ParseOptions` specify an encoding, the text fields are decoded
+/// with the encoding that `Encoding::detect()` finds for the first line with
+/// non-ASCII characters, so that all lines of a file use the same encoding.
+/// Since the reader does not look ahead, this can differ from `IgcFile`,
+/// which detects the encoding of the complete file: a first non-ASCII line
+/// that is valid Latin-1 text, like `b\xe8` (`bè`), is decoded as Latin-1,
+/// even if a later line has a byte like `\x8a` that is only a letter in
+/// Windows-1250 (`Š`). To decode a file in the same way as `IgcFile`, set
+/// the encoding of the `ParseOptions` to `Encoding::detect()` of the file.
+///
+/// With `keep_raw_lines` enabled, every `ParsedLine` also contains the
+/// original bytes and line ending of the line, which the `Writer` can write
+/// back unchanged. This keeps the G record security hash valid for all
+/// records that were not modified. Empty lines and, in lenient mode, lines
+/// that could not be parsed are then returned without a record, so that each
+/// line of the file is part of the output.
+#[derive(Debug)]
+pub struct Reader<B> {
+    lines: ByteLines<B>,
+    parser: LineParser,
+}
+
+impl<B: BufRead> Reader<B> {
+    pub fn new(buf: B) -> Reader<B> {
+        Reader::with_options(buf, ParseOptions::default())
+    }
+
+    pub fn with_options(buf: B, options: ParseOptions) -> Reader<B> {
+        Reader {
+            lines: ByteLines::new(buf),
+            parser: LineParser::new(options),
+        }
+    }
+
+    /// Creates a reader for a part of a file that starts after the lines
+    /// that `other` has seen so far, using its options, addition definitions
+    /// and date.
+    ///
+    /// The time of the last record is not known, so a midnight rollover
+    /// directly at the start of `buf` is not detected.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn continue_from<C>(buf: B, other: &Reader<C>) -> Reader<B> {
+        Reader {
+            lines: ByteLines::new(buf),
+            parser: other.parser.continued(),
+        }
+    }
+
+    /// Counts a line without returning its record. Only I, J and H records
+    /// are parsed, to keep track of the addition definitions and the date.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn skip_line(&mut self, line: &[u8]) {
+        self.parser.skip_line(line);
+    }
+}
+
+impl<B> ParserState for Reader<B> {
+    fn parser(&self) -> &LineParser {
+        &self.parser
+    }
+
+    fn parser_mut(&mut self) -> &mut LineParser {
+        &mut self.parser
+    }
+}
+
+impl<B: BufRead> Iterator for Reader<B> {
+    type Item = Result<ParsedLine>;
+
+    fn next(&mut self) -> Option<Result<ParsedLine>> {
+        loop {
+            let (line, line_ending) = match self.lines.next_line()? {
+                Ok(line) => line,
+                Err(error) => return Some(Err(error.into())),
+            };
+
+            if let Some(result) = self.parser.parse_line(line, line_ending) {
+                return Some(result);
+            }
+        }
+    }
+}
+
+/// Same as the `Reader`, for a file that is already in memory.
+///
+/// The lines are parsed directly from the bytes, without copying them into
+/// a line buffer first.
+#[derive(Debug)]
+pub struct SliceReader<'a> {
+    lines: SliceLines<'a>,
+    parser: LineParser,
+}
+
+impl<'a> SliceReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> SliceReader<'a> {
+        SliceReader::with_options(bytes, ParseOptions::default())
+    }
+
+    pub fn with_options(bytes: &'a [u8], options: ParseOptions) -> SliceReader<'a> {
+        SliceReader {
+            lines: SliceLines::new(bytes),
+            parser: LineParser::new(options),
+        }
+    }
+}
+
+impl<'a> ParserState for SliceReader<'a> {
+    fn parser(&self) -> &LineParser {
+        &self.parser
+    }
+
+    fn parser_mut(&mut self) -> &mut LineParser {
+        &mut self.parser
+    }
+}
+
+impl<'a> Iterator for SliceReader<'a> {
+    type Item = Result<ParsedLine>;
+
+    fn next(&mut self) -> Option<Result<ParsedLine>> {
+        for (line, line_ending) in &mut self.lines {
+            if let Some(result) = self.parser.parse_line(line, line_ending) {
+                return Some(result);
+            }
+        }
+        None
+    }
+}
+
+/// Options and state that the `Reader`, the `SliceReader` and the
+/// `AsyncReader` share, independent of where their lines come from.
+///
+/// The trait is implemented by the readers of this crate only.
+pub trait ParserState {
+    #[doc(hidden)]
+    fn parser(&self) -> &LineParser;
+
+    #[doc(hidden)]
+    fn parser_mut(&mut self) -> &mut LineParser;
+
+    /// Keep the original bytes of each line in `ParsedLine::raw`, and also
+    /// return empty lines and, in lenient mode, lines that could not be
+    /// parsed, without a record.
+    fn keep_raw_lines(mut self, keep: bool) -> Self where Self: Sized {
+        self.parser_mut().keep_raw_lines = keep;
+        self
+    }
+
+    /// Problems that were found in lenient mode so far
+    fn warnings(&self) -> &[ParseError] {
+        &self.parser().warnings
+    }
+
+    fn take_warnings(&mut self) -> Vec<ParseError> {
+        ::std::mem::take(&mut self.parser_mut().warnings)
+    }
+
+    /// B record addition definitions of the most recent I record
+    fn b_addition_defs(&self) -> &[AdditionDef] {
+        &self.parser().b_addition_defs
+    }
+
+    /// K record addition definitions of the most recent J record
+    fn k_addition_defs(&self) -> &[AdditionDef] {
+        &self.parser().k_addition_defs
+    }
+
+    /// Date of the `DTE` header, moved forward on midnight rollovers
+    fn date(&self) -> Option<Date> {
+        self.parser().dates.date()
+    }
+
+    /// Encoding of the text fields, if it was set in the `ParseOptions` or
+    /// a line with non-ASCII characters was read
+    fn encoding(&self) -> Option<Encoding> {
+        self.parser().encoding
+    }
+}
+
+/// State of a reader that is independent of where the lines come from, see
+/// `ParserState`
+#[derive(Debug)]
+pub struct LineParser {
+    pub(crate) line_number: usize,
+    pub(crate) options: ParseOptions,
+    pub(crate) warnings: Vec<ParseError>,
+    pub(crate) keep_raw_lines: bool,
+    pub(crate) encoding: Option<Encoding>,
+    pub(crate) b_addition_defs: Vec<AdditionDef>,
+    pub(crate) k_addition_defs: Vec<AdditionDef>,
+    pub(crate) dates: DateTracker,
+}
+
+impl LineParser {
+    pub(crate) fn new(options: ParseOptions) -> LineParser {
+        LineParser {
+            line_number: 0,
+            options,
+            warnings: Vec::new(),
+            keep_raw_lines: false,
+            encoding: options.encoding,
+            b_addition_defs: Vec::new(),
+            k_addition_defs: Vec::new(),
+            dates: DateTracker::new(),
+        }
+    }
+
+    /// Copy of the state without the warnings, see `Reader::continue_from()`
+    #[cfg(feature = "parallel")]
+    fn continued(&self) -> LineParser {
+        let mut dates = DateTracker::new();
+        if let Some(date) = self.dates.date() {
+            dates.set_date(date);
+        }
+
+        LineParser {
+            line_number: self.line_number,
+            options: self.options,
+            warnings: Vec::new(),
+            keep_raw_lines: self.keep_raw_lines,
+            encoding: self.encoding,
+            b_addition_defs: self.b_addition_defs.clone(),
+            k_addition_defs: self.k_addition_defs.clone(),
+            dates,
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    fn skip_line(&mut self, line: &[u8]) {
+        self.line_number += 1;
+        self.detect_encoding(line);
+        if let Some(b'I') | Some(b'J') | Some(b'H') = line.first() {
+            let num_warnings = self.warnings.len();
+            let _ = self.parse(line);
+            self.warnings.truncate(num_warnings);
+        }
+    }
+
+    /// Parses the next line of the file. Returns `None` for lines that are
+    /// skipped, i.e. empty lines and, in lenient mode, lines that could not
+    /// be parsed, unless the raw lines are kept.
+    pub(crate) fn parse_line(&mut self, line: &[u8], line_ending: LineEnding) -> Option<Result<ParsedLine>> {
+        self.line_number += 1;
+
+        if line.is_empty() {
+            return self.unparsed(line, line_ending);
+        }
+
+        self.detect_encoding(line);
+
+        let line_number = self.line_number;
+        let record = match self.parse(line) {
+            Ok(record) => record,
+            Err(error) => return self.parse_error(error, line, line_ending),
+        };
+        let datetime = self.datetime(&record);
+        let raw = if self.keep_raw_lines {
+            Some(RawLine { bytes: line.to_vec(), line_ending })
+        } else {
+            None
+        };
+
+        Some(Ok(ParsedLine { line_number, record: Some(record), datetime, raw }))
+    }
+
+    /// Returns the error of a line that could not be parsed. In lenient
+    /// mode, the error is only a warning and the line is handled like an
+    /// empty line.
+    fn parse_error(&mut self, error: Error, line: &[u8], line_ending: LineEnding) -> Option<Result<ParsedLine>> {
+        match error.on_line(self.line_number) {
+            Error::Parse(error) if self.options.mode == ParseMode::Lenient => {
+                self.warnings.push(error);
+                self.unparsed(line, line_ending)
+            },
+            error => Some(Err(error)),
+        }
+    }
+
+    /// Returns a line without a record, or `None` if the raw lines are not
+    /// kept.
+    fn unparsed(&self, line: &[u8], line_ending: LineEnding) -> Option<Result<ParsedLine>> {
+        if !self.keep_raw_lines {
+            return None;
+        }
+
+        let raw = RawLine { bytes: line.to_vec(), line_ending };
+        Some(Ok(ParsedLine { line_number: self.line_number, record: None, datetime: None, raw: Some(raw) }))
+    }
+
+    fn parse(&mut self, line: &[u8]) -> Result<Record> {
+        let record = match line[0] {
+            b'B' => BRecord::parse_with_additions(line, &self.b_addition_defs).map(Record::B),
+            b'K' => KRecord::parse_with_additions(line, &self.k_addition_defs).map(Record::K),
+            _ => Record::parse_encoded(line, self.encoding),
+        };
+
+        let record = match record {
+            Ok(record) => record,
+            Err(error) => if self.options.mode == ParseMode::Lenient && error.kind() == Some(ErrorKind::MissingAddition) {
+                // keep the fix and drop the additions that don't fit into the line
+                let record = match line[0] {
+                    b'B' => BRecord::parse_with_additions(line, &fitting_addition_defs(&self.b_addition_defs, line)).map(Record::B)?,
+                    _ => KRecord::parse_with_additions(line, &fitting_addition_defs(&self.k_addition_defs, line)).map(Record::K)?,
+                };
+                self.warn(error);
+                record
+            } else {
+                return Err(error);
+            },
+        };
+
+        match self.options.mode {
+            ParseMode::Standard => {},
+            ParseMode::Strict => if let Some(error) = validate(line, &record).into_iter().next() {
+                return Err(error);
+            },
+            ParseMode::Lenient => for error in validate(line, &record) {
+                self.warn(error);
+            },
+        }
+
+        match record {
+            Record::I(ref record) => self.b_addition_defs = record.addition_defs.clone(),
+            Record::J(ref record) => self.k_addition_defs = record.addition_defs.clone(),
+            Record::H(ref record) if record.code == HeaderCode::DTE => {
+                if let HeaderValue::Date { date, .. } = record.value() {
+                    self.dates.set_date(date);
+                }
+            },
+            _ => {},
+        }
+
+        Ok(record)
+    }
+
+    fn detect_encoding(&mut self, line: &[u8]) {
+        if self.encoding.is_none() && !line.is_ascii() {
+            self.encoding = Some(Encoding::detect(line));
+        }
+    }
+
+    fn warn(&mut self, error: Error) {
+        if let Error::Parse(error) = error.on_line(self.line_number) {
+            self.warnings.push(error);
+        }
+    }
+
+    fn datetime(&mut self, record: &Record) -> Option<DateTime> {
+        match *record {
+            Record::B(ref record) => self.dates.datetime(record.time)
+                .map(|datetime| with_decimal_seconds(datetime, record)),
+            Record::E(ref record) => self.dates.datetime(record.time),
+            Record::F(ref record) => self.dates.datetime(record.time),
+            Record::K(ref record) => self.dates.datetime(record.time)
+                .map(|datetime| with_decimal_seconds(datetime, record)),
+            _ => None,
+        }
+    }
+}
+
+fn fitting_addition_defs(defs: &[AdditionDef], line: &[u8]) -> Vec<AdditionDef> {
+    defs.iter()
+        .filter(|def| def.end_byte as usize <= line.len())
+        .cloned()
+        .collect()
+}
+
+fn with_decimal_seconds<T: AdditionSupport>(datetime: DateTime, record: &T) -> DateTime {
+    match record.decimal_seconds() {
+        None => datetime,
+        Some(value) => datetime.with_decimal_seconds(value),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use super::*;
+    use ::Time;
+    use ::utils::additions::AdditionCode;
+
+    #[test]
+    fn test_additions() {
+        let input = b"I023638FXA3941ENL\r\nB1414065016925N00953112EA0216402287003009\r\nJ010810HDT\r\n\r\nK160310090\r\n";
+        let lines: Vec<_> = Reader::new(Cursor::new(&input[..])).map(Result::unwrap).collect();
+        assert_eq!(lines.len(), 4);
+
+        assert_eq!(lines[0].line_number, 1);
+        match lines[0].record {
+            Some(Record::I(ref record)) => assert_eq!(record.num_additions, 2),
+            ref record => panic!("unexpected record: {:?}", record),
+        }
+
+        assert_eq!(lines[1].line_number, 2);
+        match lines[1].record {
+            Some(Record::B(ref record)) => {
+                assert_eq!(record.fix_accuracy(), Some(3));
+                assert_eq!(record.enl(), Some(9));
+            },
+            ref record => panic!("unexpected record: {:?}", record),
+        }
+
+        assert_eq!(lines[3].line_number, 5);
+        match lines[3].record {
+            Some(Record::K(ref record)) => assert_eq!(record.get_addition(&AdditionCode::HDT), Some(&b"090"[..])),
+            ref record => panic!("unexpected record: {:?}", record),
+        }
+    }
+
+    #[test]
+    fn test_datetimes() {
+        let input = b"HFDTEDATE:311215,01\nI013637TDS\nB2359595016925N00953112EA021640228700\nE235959PEV\nB2359595016925N00953112EA021640228720\nB0000005016925N00953112EA021640228700\nF000001010203\n";
+        let lines: Vec<_> = Reader::new(Cursor::new(&input[..])).map(Result::unwrap).collect();
+        let datetimes: Vec<_> = lines.iter().map(|line| line.datetime).collect();
+
+        let date = Date::new(15, 12, 31);
+        assert_eq!(datetimes, vec![
+            None,
+            None,
+            Some(DateTime::new(date, Time::from_hms(23, 59, 59)).with_decimal_seconds(0.)),
+            Some(DateTime::new(date, Time::from_hms(23, 59, 59))),
+            Some(DateTime::new(date, Time::from_hms(23, 59, 59)).with_decimal_seconds(0.2)),
+            Some(DateTime::new(date.next_day(), Time::from_hms(0, 0, 0)).with_decimal_seconds(0.)),
+            Some(DateTime::new(date.next_day(), Time::from_hms(0, 0, 1))),
+        ]);
+        assert!(datetimes[2] < datetimes[4]);
+    }
+
+    #[test]
+    fn test_raw_lines() {
+        let input = b"HFPLTPILOT:J\xf6rg\r\nLPLTfoo\nLPLTbar";
+        let lines: Vec<_> = Reader::new(Cursor::new(&input[..])).map(Result::unwrap).collect();
+        assert!(lines.iter().all(|line| line.raw.is_none()));
+
+        let lines: Vec<_> = Reader::new(Cursor::new(&input[..])).keep_raw_lines(true).map(Result::unwrap).collect();
+        let raw: Vec<_> = lines.into_iter().map(|line| line.raw.unwrap()).collect();
+        assert_eq!(raw, vec![
+            RawLine { bytes: b"HFPLTPILOT:J\xf6rg".to_vec(), line_ending: LineEnding::CrLf },
+            RawLine { bytes: b"LPLTfoo".to_vec(), line_ending: LineEnding::Lf },
+            RawLine { bytes: b"LPLTbar".to_vec(), line_ending: LineEnding::None },
+        ]);
+    }
+
+    #[test]
+    fn test_unparsed_lines() {
+        let input = b"LPLTfoo\r\n\r\nB14140\r\n\x1a";
+        for options in &[ParseOptions::default(), ParseOptions::lenient()] {
+            let lines: Vec<_> = Reader::with_options(Cursor::new(&input[..]), *options).collect();
+            assert_eq!(lines.len(), if options.mode == ParseMode::Lenient { 1 } else { 3 });
+
+            let lines: Vec<_> = Reader::with_options(Cursor::new(&input[..]), *options).keep_raw_lines(true).collect();
+            assert_eq!(lines.len(), 4);
+            assert!(lines[0].is_ok());
+            match lines[1] {
+                Ok(ref line) => {
+                    assert_eq!(line.line_number, 2);
+                    assert!(line.record.is_none());
+                    assert_eq!(line.raw, Some(RawLine { bytes: Vec::new(), line_ending: LineEnding::CrLf }));
+                },
+                ref line => panic!("unexpected line: {:?}", line),
+            }
+            match lines[2] {
+                Ok(ref line) if options.mode == ParseMode::Lenient => {
+                    assert!(line.record.is_none());
+                    assert_eq!(line.raw, Some(RawLine { bytes: b"B14140".to_vec(), line_ending: LineEnding::CrLf }));
+                },
+                Err(ref error) if options.mode != ParseMode::Lenient => assert_eq!(error.kind(), Some(ErrorKind::BadTime)),
+                ref line => panic!("unexpected line: {:?}", line),
+            }
+            match lines[3] {
+                Ok(ref line) if options.mode == ParseMode::Lenient => assert_eq!(line.raw.as_ref().unwrap().bytes, b"\x1a"),
+                Err(ref error) if options.mode != ParseMode::Lenient => {
+                    assert_eq!(error.to_string(), "line 4: unknown record type at column 1 (record type): \u{1a}");
+                },
+                ref line => panic!("unexpected line: {:?}", line),
+            }
+        }
+    }
+
+    #[test]
+    fn test_encoding() {
+        let input = b"LPLTfoo\nHFPLTPILOT:\x8aebesta\nLPLTb\xe8\n";
+        let texts = |reader: &mut Reader<Cursor<&[u8]>>| -> Vec<String> {
+            reader.map(|line| match line.unwrap().record {
+                Some(Record::H(record)) => record.text,
+                Some(Record::L(record)) => record.text,
+                record => panic!("unexpected record: {:?}", record),
+            }).collect()
+        };
+
+        let mut reader = Reader::new(Cursor::new(&input[..]));
+        assert_eq!(reader.encoding(), None);
+        assert_eq!(texts(&mut reader), vec!["foo", "\u{160}ebesta", "b\u{10d}"]);
+        assert_eq!(reader.encoding(), Some(Encoding::Windows1250));
+
+        let options = ParseOptions::default().encoding(Encoding::Latin1);
+        let mut reader = Reader::with_options(Cursor::new(&input[..]), options);
+        assert_eq!(texts(&mut reader), vec!["foo", "\u{8a}ebesta", "b\u{e8}"]);
+        assert_eq!(reader.encoding(), Some(Encoding::Latin1));
+
+        let options = ParseOptions::default().encoding(Encoding::Utf8);
+        let mut reader = Reader::with_options(Cursor::new(&input[..]), options);
+        assert!(reader.nth(1).unwrap().is_err());
+    }
+
+    #[test]
+    fn test_strict() {
+        let input = b"HFDTE040516\nB2514065016925N00953112EA0216402287\nB1414065016925N00953112EA0216402287\n";
+        let results: Vec<_> = Reader::with_options(Cursor::new(&input[..]), ParseOptions::strict()).collect();
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert_eq!(results[1].as_ref().unwrap_err().to_string(), "line 2: bad time at column 2 (time): B2514065016925N00953112EA0216402287");
+        assert!(results[2].is_ok());
+
+        let results: Vec<_> = Reader::new(Cursor::new(&input[..])).collect();
+        assert!(results.iter().all(Result::is_ok));
+    }
+
+    #[test]
+    fn test_lenient() {
+        let input = b"I033638FXA3941ENL\nB2514065016925N00953112EA0216402287003\nB14140\nB1414065016925N00953112EA0216402287003309\n";
+        let mut reader = Reader::with_options(Cursor::new(&input[..]), ParseOptions::lenient());
+        let lines: Vec<_> = reader.by_ref().map(Result::unwrap).collect();
+        assert_eq!(lines.len(), 3);
+        match lines[1].record {
+            Some(Record::B(ref record)) => {
+                assert_eq!(record.fix_accuracy(), Some(3));
+                assert_eq!(record.enl(), None);
+            },
+            ref record => panic!("unexpected record: {:?}", record),
+        }
+        match lines[2].record {
+            Some(Record::B(ref record)) => assert_eq!(record.enl(), Some(309)),
+            ref record => panic!("unexpected record: {:?}", record),
+        }
+
+        let warnings: Vec<_> = reader.warnings().iter().map(|w| (w.line_number, w.kind)).collect();
+        assert_eq!(warnings, vec![
+            (Some(1), ErrorKind::AdditionCountMismatch),
+            (Some(2), ErrorKind::MissingAddition),
+            (Some(2), ErrorKind::BadTime),
+            (Some(3), ErrorKind::BadTime),
+        ]);
+        assert_eq!(reader.take_warnings().len(), 4);
+        assert!(reader.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_addition_out_of_range() {
+        for input in &[&b"I010003FXA\nB1414065016925N00953112EA021640228700\n"[..], b"I013836FXA\nB1414065016925N00953112EA021640228700\n"] {
+            let lines: Vec<_> = Reader::new(Cursor::new(*input)).collect();
+            assert_eq!(lines[0].as_ref().unwrap_err().kind(), Some(ErrorKind::AdditionOutOfRange));
+            match lines[1] {
+                Ok(ParsedLine { record: Some(Record::B(ref record)), .. }) => assert_eq!(record.fix_accuracy(), None),
+                ref line => panic!("unexpected line: {:?}", line),
+            }
+
+            // lenient mode ignores the definitions with a warning
+            let mut reader = Reader::with_options(Cursor::new(*input), ParseOptions::lenient());
+            let lines: Vec<_> = reader.by_ref().map(Result::unwrap).collect();
+            assert_eq!(lines.len(), 1);
+            let warnings: Vec<_> = reader.warnings().iter().map(|w| (w.line_number, w.kind)).collect();
+            assert_eq!(warnings, vec![(Some(1), ErrorKind::AdditionOutOfRange)]);
+        }
+    }
+
+    #[test]
+    fn test_missing_additions() {
+        let input = b"I013638FXA\nB1414065016925N00953112EA0216402287\n";
+        let mut reader = Reader::new(Cursor::new(&input[..]));
+        assert!(reader.next().unwrap().is_ok());
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.to_string(), "line 2: missing addition at column 36 (FXA): B1414065016925N00953112EA0216402287");
+        assert!(reader.next().is_none());
+    }
+}
@@ -0,0 +1,163 @@
+use ::Result;
+use ::utils::fields::{Field, diagnose, is_code};
+use ::utils::text::{Encoding, text_field};
+use ::prelude::*;
+
+const FIELDS: &[Field] = &[
+    Field::code("manufacturer"),
+    Field::code("serial number"),
+];
+
+// Examples:
+//
+// AFLA6NG
+// ALXN00529FLIGHT:1
+// AXLKSEM
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ARecord {
+    /// Three-character manufacturer code
+    pub manufacturer: String,
+
+    /// Unique FR serial number (three or more characters)
+    pub id: String,
+
+    /// Optional additional text, e.g. `FLIGHT:1`
+    pub extension: Option<String>,
+}
+
+impl ARecord {
+    pub fn parse(line: &[u8]) -> Result<ARecord> {
+        ARecord::parse_encoded(line, None)
+    }
+
+    /// Like `parse()`, but decodes the text with the given `encoding`.
+    pub fn parse_with_encoding(line: &[u8], encoding: Encoding) -> Result<ARecord> {
+        ARecord::parse_encoded(line, Some(encoding))
+    }
+
+    pub(crate) fn parse_encoded(line: &[u8], encoding: Option<Encoding>) -> Result<ARecord> {
+        let id_end = id_end(line).ok_or_else(|| diagnose(line, FIELDS))?;
+
+        let manufacturer = text_field(line, 1, 4, "manufacturer", encoding)?;
+        let id = text_field(line, 4, id_end, "serial number", encoding)?;
+        let extension = if id_end == line.len() {
+            None
+        } else {
+            Some(text_field(line, id_end, line.len(), "extension", encoding)?)
+        };
+
+        Ok(ARecord { manufacturer, id, extension })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let extension = self.extension.as_ref().map_or("", String::as_str);
+        format!("A{}{}{}", self.manufacturer, self.id, extension).into_bytes()
+    }
+}
+
+/// End of the serial number, which is the shortest run of (at least three)
+/// code characters that is followed by a valid extension or the end of the
+/// line.
+fn id_end(line: &[u8]) -> Option<usize> {
+    if line.len() < 7 || line[0] != b'A' || !is_code(&line[1..7]) {
+        return None;
+    }
+
+    let mut end = 7;
+    while !is_extension(&line[end..]) {
+        if !line.get(end).is_some_and(|b| is_code(&[*b])) {
+            return None;
+        }
+        end += 1;
+    }
+    Some(end)
+}
+
+/// Checks if the rest of the line is empty or a valid single-line extension,
+/// i.e. `FLIGHT:` or any non-code character followed by some text.
+fn is_extension(rest: &[u8]) -> bool {
+    match rest.first() {
+        None => true,
+        Some(_) if rest.starts_with(b"FLIGHT:") => !rest.contains(&b'\n'),
+        Some(b) => !is_code(&[*b]) && !rest[1..].contains(&b'\n'),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use regex::bytes::Regex;
+    use super::*;
+
+    /// Original regex based parser, which the hand-written parser has to
+    /// behave exactly like
+    fn parse_regex(line: &[u8]) -> Result<ARecord> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"(?x-u)
+                ^A
+                ([A-Z0-9]{3})               # manufacturer
+                ([A-Z0-9]{3,}?)             # unique FR serial number
+                (FLIGHT:.*|[^A-Z0-9].*)?    # optional extension
+                $
+            ").unwrap();
+        }
+
+        let cap = RE.captures(line).ok_or_else(|| diagnose(line, FIELDS))?;
+
+        let manufacturer = text_field(line, 1, 4, "manufacturer", None)?;
+        let id = text_field(line, 4, cap.get(2).unwrap().end(), "serial number", None)?;
+        let extension = match cap.get(3) {
+            None => None,
+            Some(m) => Some(text_field(line, m.start(), m.end(), "extension", None)?),
+        };
+
+        Ok(ARecord { manufacturer, id, extension })
+    }
+
+    fn assert_a_record(bytes: &[u8], manufacturer: &str, id: &str, extension: Option<&str>) {
+        let record = ARecord::parse(bytes).unwrap();
+        assert_eq!(record.manufacturer, manufacturer);
+        assert_eq!(record.id, id);
+        assert_eq!(record.extension.as_deref(), extension);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_a_record(b"AFLA6NG", "FLA", "6NG", None);
+        assert_a_record(b"AFLA5HV", "FLA", "5HV", None);
+        assert_a_record(b"AXLKSEM", "XLK", "SEM", None);
+        assert_a_record(b"ALXN00529FLIGHT:1", "LXN", "00529", Some("FLIGHT:1"));
+        assert_a_record(b"ALXNGGYFLIGHT:1", "LXN", "GGY", Some("FLIGHT:1"));
+        assert_a_record(b"AXXXABC:some text", "XXX", "ABC", Some(":some text"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(ARecord::parse(b"A").is_err());
+        assert!(ARecord::parse(b"AFLA").is_err());
+        assert!(ARecord::parse(b"AFLA6N").is_err());
+        assert!(ARecord::parse(b"Afla6NG").is_err());
+    }
+
+    #[test]
+    fn test_to_bytes() {
+        for line in &[&b"AFLA6NG"[..], b"ALXN00529FLIGHT:1", b"AXXXABC:some text"] {
+            assert_eq!(ARecord::parse(line).unwrap().to_bytes(), line.to_vec());
+        }
+    }
+
+    proptest! {
+        #[test]
+        #[allow(unused_must_use)]
+        fn parse_doesnt_crash(s in r"\PC*") {
+            ARecord::parse(s.as_bytes());
+        }
+
+        #[test]
+        fn parse_matches_regex(s in r"\PC*|A[A-Z0-9]{3,8}(FLIGHT:?)?[A-Z0-9:a ]{0,3}(\n\PC{0,3})?") {
+            let result = ARecord::parse(s.as_bytes()).map_err(|error| error.to_string());
+            prop_assert_eq!(result, parse_regex(s.as_bytes()).map_err(|error| error.to_string()));
+        }
+    }
+}
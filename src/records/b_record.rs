@@ -1,53 +1,127 @@
-use regex::bytes::Regex;
-
-use ::{Error, Result, Time};
-use ::utils::coordinates::{parse_latitude_unchecked, parse_longitude_unchecked};
+use ::{Error, ErrorKind, Result, Time};
+use ::utils::coordinates::{parse_latitude_unchecked, parse_longitude_unchecked, format_latitude, format_longitude};
+use ::utils::fields::{Field, check};
 use ::utils::num::parse_int;
 use ::utils::additions::*;
+use ::prelude::*;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct BRecord {
     pub time: Time,
     pub latitude: f64,
     pub longitude: f64,
     pub is_valid: bool,
-    pub altitude_pressure: i16,
-    pub altitude_gps: i16,
+
+    /// Pressure altitude in metres, or `None` if it was not recorded
+    /// (`00000`). An altitude of zero is `-0000` in the file.
+    pub altitude_pressure: Option<i32>,
+
+    /// GNSS altitude in metres, or `None` if it was not recorded or not
+    /// available, e.g. during 2D fixes (`00000`). An altitude of zero is
+    /// `-0000` in the file.
+    pub altitude_gps: Option<i32>,
+
     pub additions: AdditionsMap,
 }
 
 // B 13 05 10 52 40678 N 007 48278 W A 00779 00769 033011
+const FIELDS: &[Field] = &[
+    Field::time("time"),
+    Field::latitude(),
+    Field::longitude(),
+    Field::new("fix validity", 1, ErrorKind::InvalidRecord, is_validity),
+    Field::altitude("pressure altitude"),
+    Field::altitude("gps altitude"),
+];
+
+/// Borrowed variant of `BRecord`, which does not allocate and only decodes
+/// the additions when they are accessed
+#[derive(Debug, Clone, Copy)]
+pub struct BRecordRef<'a> {
+    pub time: Time,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub is_valid: bool,
+    pub altitude_pressure: Option<i32>,
+    pub altitude_gps: Option<i32>,
+    pub additions: AdditionsRef<'a>,
+}
+
 impl BRecord {
     pub fn parse(line: &[u8]) -> Result<BRecord> {
-        Self::parse_with_additions(line, &vec![])
+        Self::parse_with_additions(line, &[])
     }
 
-    pub fn parse_with_additions(line: &[u8], addition_defs: &Vec<AdditionDef>) -> Result<BRecord> {
-        lazy_static! {
-            static ref RE: Regex = Regex::new(r"(?x-u)
-                ^B                     # record typ
-                (\d{6})                # UTC time
-                (\d{7}[NS])            # latitude
-                (\d{8}[EW])            # longitude
-                ([AV])                 # validity
-                (\d{5}|-\d{4})         # gps altitude
-                (\d{5}|-\d{4})         # pressure altitude
-                (.*)                   # additions
-            ").unwrap();
+    pub fn parse_with_additions(line: &[u8], addition_defs: &[AdditionDef]) -> Result<BRecord> {
+        BRecordRef::parse_with_additions(line, addition_defs).map(|record| record.to_record())
+    }
+
+    /// Formats the record without any additions.
+    ///
+    /// A missing altitude is written as `00000` and an altitude of zero as
+    /// `-0000`, so that both are read back unchanged. Other readers that
+    /// parse the field as a plain number read both of them as zero.
+    ///
+    /// Returns an error if an altitude is outside of -9999 to 99999 metres,
+    /// which the five characters of the altitude fields can not represent.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut line = format!(
+            "B{}{}{}{}",
+            self.time.format(),
+            format_latitude(self.latitude),
+            format_longitude(self.longitude),
+            if self.is_valid { 'A' } else { 'V' },
+        ).into_bytes();
+
+        for &(value, field) in &[(self.altitude_pressure, "pressure altitude"), (self.altitude_gps, "gps altitude")] {
+            let column = line.len() + 1;
+            match format_altitude(value) {
+                Some(text) => line.extend_from_slice(text.as_bytes()),
+                None => return Err(Error::parse(ErrorKind::BadAltitude, &line).in_field(column, field)),
+            }
         }
+        Ok(line)
+    }
+
+    /// Formats the record with its additions at the byte positions of the
+    /// given I record addition definitions.
+    pub fn to_bytes_with_additions(&self, addition_defs: &[AdditionDef]) -> Result<Vec<u8>> {
+        let mut line = self.to_bytes()?;
+        addition_defs.format(&mut line, &self.additions)?;
+        Ok(line)
+    }
+
+    /// Latitude of the fix using the `latitude` field and the `LAD` addition if
+    /// it exists.
+    pub fn latitude(&self) -> f64 {
+        with_decimals(self.latitude, self.additional_latitude_decimals())
+    }
 
-        let cap = RE.captures(line).ok_or_else(|| Error::invalid_record(line))?;
+    /// Latitude of the fix using the `longitude` field and the `LOD` addition if
+    /// it exists.
+    pub fn longitude(&self) -> f64 {
+        with_decimals(self.longitude, self.additional_longitude_decimals())
+    }
+}
 
-        let time = Time::parse_unchecked(&cap[1]);
-        let latitude = parse_latitude_unchecked(&cap[2]);
-        let longitude = parse_longitude_unchecked(&cap[3]);
-        let is_valid = &cap[4] == b"A";
-        let altitude_pressure = parse_int(&cap[5]).unwrap();
-        let altitude_gps = parse_int(&cap[6]).unwrap();
+impl<'a> BRecordRef<'a> {
+    pub fn parse(line: &'a [u8]) -> Result<BRecordRef<'a>> {
+        Self::parse_with_additions(line, &[])
+    }
 
-        let additions = addition_defs.parse(&line)?;
+    pub fn parse_with_additions(line: &'a [u8], addition_defs: &'a [AdditionDef]) -> Result<BRecordRef<'a>> {
+        check(line, b'B', FIELDS)?;
 
-        Ok(BRecord {
+        let time = Time::parse_unchecked(&line[1..7]);
+        let latitude = parse_latitude_unchecked(&line[7..15]);
+        let longitude = parse_longitude_unchecked(&line[15..24]);
+        let is_valid = line[24] == b'A';
+        let altitude_pressure = parse_altitude(&line[25..30]);
+        let altitude_gps = parse_altitude(&line[30..35]);
+
+        let additions = AdditionsRef::new(line, addition_defs)?;
+
+        Ok(BRecordRef {
             time,
             latitude,
             longitude,
@@ -58,30 +132,70 @@ impl BRecord {
         })
     }
 
+    /// Copies the record into an owned `BRecord`.
+    pub fn to_record(&self) -> BRecord {
+        BRecord {
+            time: self.time,
+            latitude: self.latitude,
+            longitude: self.longitude,
+            is_valid: self.is_valid,
+            altitude_pressure: self.altitude_pressure,
+            altitude_gps: self.altitude_gps,
+            additions: self.additions.to_map(),
+        }
+    }
+
     /// Latitude of the fix using the `latitude` field and the `LAD` addition if
     /// it exists.
     pub fn latitude(&self) -> f64 {
-        match self.additional_latitude_decimals() {
-            None => return self.latitude,
-            Some(value) => if self.latitude.is_sign_negative() {
-                self.latitude - value
-            } else {
-                self.latitude + value
-            },
-        }
+        with_decimals(self.latitude, self.additional_latitude_decimals())
     }
 
     /// Latitude of the fix using the `longitude` field and the `LOD` addition if
     /// it exists.
     pub fn longitude(&self) -> f64 {
-        match self.additional_longitude_decimals() {
-            None => self.longitude,
-            Some(value) => if self.longitude.is_sign_negative() {
-                self.longitude - value
-            } else {
-                self.longitude + value
-            },
-        }
+        with_decimals(self.longitude, self.additional_longitude_decimals())
+    }
+}
+
+fn with_decimals(value: f64, decimals: Option<f64>) -> f64 {
+    match decimals {
+        None => value,
+        Some(decimals) => if value.is_sign_negative() {
+            value - decimals
+        } else {
+            value + decimals
+        },
+    }
+}
+
+fn is_validity(bytes: &[u8]) -> bool {
+    bytes == b"A" || bytes == b"V"
+}
+
+/// Parses a five digit or `-dddd` altitude, where `00000` means that the
+/// altitude was not recorded.
+fn parse_altitude(bytes: &[u8]) -> Option<i32> {
+    if bytes == b"00000" {
+        None
+    } else {
+        parse_int(bytes)
+    }
+}
+
+/// Formats an altitude as five digits or as `-dddd` if it is negative, and
+/// a missing altitude as `00000`. An altitude of zero is written as `-0000`,
+/// so that it is not read back as a missing altitude.
+///
+/// Returns `None` for values outside of -9999 to 99999, which can not be
+/// represented.
+fn format_altitude(value: Option<i32>) -> Option<String> {
+    match value {
+        None => Some("00000".to_string()),
+        Some(0) => Some("-0000".to_string()),
+        Some(value) if !(-9999..=99999).contains(&value) => None,
+        Some(value) if value < 0 => Some(format!("-{:04}", -value)),
+        Some(value) => Some(format!("{:05}", value)),
     }
 }
 
@@ -91,19 +205,56 @@ impl AdditionSupport for BRecord {
     }
 }
 
+impl<'a> AdditionSupport for BRecordRef<'a> {
+    fn get_addition(&self, code: &AdditionCode) -> Option<&[u8]> {
+        self.additions.get(code)
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use regex::bytes::Regex;
+    use ::utils::fields::diagnose;
     use super::*;
 
+    /// Original regex based parser, which the fixed-offset parser has to
+    /// behave exactly like
+    fn parse_regex(line: &[u8]) -> Result<BRecord> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"(?x-u)
+                ^B                     # record typ
+                (\d{6})                # UTC time
+                (\d{7}[NS])            # latitude
+                (\d{8}[EW])            # longitude
+                ([AV])                 # validity
+                (\d{5}|-\d{4})         # pressure altitude
+                (\d{5}|-\d{4})         # gps altitude
+                (.*)                   # additions
+            ").unwrap();
+        }
+
+        let cap = RE.captures(line).ok_or_else(|| diagnose(line, FIELDS))?;
+
+        Ok(BRecord {
+            time: Time::parse_unchecked(&cap[1]),
+            latitude: parse_latitude_unchecked(&cap[2]),
+            longitude: parse_longitude_unchecked(&cap[3]),
+            is_valid: &cap[4] == b"A",
+            altitude_pressure: parse_altitude(&cap[5]),
+            altitude_gps: parse_altitude(&cap[6]),
+            additions: AdditionsMap::new(),
+        })
+    }
+
     #[test]
     fn test_example_1() {
         let record = BRecord::parse(b"B1414065016925N00953112EA021640228700309").unwrap();
-        assert_eq!(record.time, Time::from_hms(14, 14, 06));
+        assert_eq!(record.time, Time::from_hms(14, 14, 6));
         assert_relative_eq!(record.latitude, 50.28208333333333);
         assert_relative_eq!(record.longitude, 9.8852);
-        assert_eq!(record.is_valid, true);
-        assert_eq!(record.altitude_pressure, 2164);
-        assert_eq!(record.altitude_gps, 2287);
+        assert!(record.is_valid);
+        assert_eq!(record.altitude_pressure, Some(2164));
+        assert_eq!(record.altitude_gps, Some(2287));
         assert_eq!(record.additions.len(), 0);
     }
 
@@ -114,20 +265,39 @@ mod tests {
             AdditionDef::new(AdditionCode::SIU, 39, 40),
         ];
         let record = BRecord::parse_with_additions(b"B1414065016925N00953112EA021640228700309", &addition_defs).unwrap();
-        assert_eq!(record.time, Time::from_hms(14, 14, 06));
+        assert_eq!(record.time, Time::from_hms(14, 14, 6));
         assert_relative_eq!(record.latitude, 50.28208333333333);
         assert_relative_eq!(record.latitude(), 50.28208333333333);
         assert_relative_eq!(record.longitude, 9.8852);
         assert_relative_eq!(record.longitude(), 9.8852);
-        assert_eq!(record.is_valid, true);
-        assert_eq!(record.altitude_pressure, 2164);
-        assert_eq!(record.altitude_gps, 2287);
+        assert!(record.is_valid);
+        assert_eq!(record.altitude_pressure, Some(2164));
+        assert_eq!(record.altitude_gps, Some(2287));
         assert_eq!(record.additions.len(), 2);
         assert_eq!(record.additions.get(&AdditionCode::FXA).unwrap(), b"003");
         assert_eq!(record.additions.get(&AdditionCode::SIU).unwrap(), b"09");
         assert_eq!(record.fix_accuracy(), Some(3));
     }
 
+    #[test]
+    fn test_altitudes() {
+        let record = BRecord::parse(b"B1414065016925N00953112EA4000099999").unwrap();
+        assert_eq!(record.altitude_pressure, Some(40000));
+        assert_eq!(record.altitude_gps, Some(99999));
+
+        let record = BRecord::parse(b"B1414065016925N00953112EV0216400000").unwrap();
+        assert_eq!(record.altitude_pressure, Some(2164));
+        assert_eq!(record.altitude_gps, None);
+
+        let record = BRecord::parse(b"B1414065016925N00953112EA00000-0012").unwrap();
+        assert_eq!(record.altitude_pressure, None);
+        assert_eq!(record.altitude_gps, Some(-12));
+
+        let record = BRecord::parse(b"B1414065016925N00953112EA-000000001").unwrap();
+        assert_eq!(record.altitude_pressure, Some(0));
+        assert_eq!(record.altitude_gps, Some(1));
+    }
+
     #[test]
     fn test_lat_lon_precision() {
         let addition_defs = vec![
@@ -183,11 +353,131 @@ mod tests {
         assert_eq!(record.heading_magnetic(), None);
     }
 
+    #[test]
+    fn test_borrowed() {
+        let addition_defs = vec![
+            AdditionDef::new(AdditionCode::FXA, 36, 38),
+            AdditionDef::new(AdditionCode::LAD, 39, 40),
+        ];
+        let line = b"B1414065016925S00953112EA021640228700312";
+        let record = BRecordRef::parse_with_additions(line, &addition_defs).unwrap();
+        assert_eq!(record.time, Time::from_hms(14, 14, 6));
+        assert_eq!(record.altitude_pressure, Some(2164));
+        assert_eq!(record.additions.get(&AdditionCode::FXA), Some(&b"003"[..]));
+        assert_eq!(record.fix_accuracy(), Some(3));
+        assert_relative_eq!(record.latitude(), -(50. + 16.92512 / 60.));
+
+        let owned = record.to_record();
+        assert_eq!(owned.additions, record.additions.to_map());
+        assert_relative_eq!(owned.latitude(), record.latitude());
+        assert_eq!(owned.to_bytes_with_additions(&addition_defs).unwrap(), line.to_vec());
+
+        assert!(BRecordRef::parse_with_additions(&line[..38], &addition_defs).is_err());
+
+        let addition_defs = [AdditionDef::new(AdditionCode::FXA, 0, 3)];
+        let error = BRecordRef::parse_with_additions(line, &addition_defs).unwrap_err();
+        assert_eq!(error.kind(), Some(ErrorKind::AdditionOutOfRange));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = BRecord::parse(b"B1414065016925N00953112XA0216402287").unwrap_err();
+        assert_eq!(error.to_string(), "bad coordinate at column 16 (longitude): B1414065016925N00953112XA0216402287");
+
+        let error = BRecord::parse(b"B1414065016925N00953112EX0216402287").unwrap_err();
+        assert_eq!(error.kind(), Some(ErrorKind::InvalidRecord));
+        assert_eq!(error.to_string(), "invalid record at column 25 (fix validity): B1414065016925N00953112EX0216402287");
+
+        let error = BRecord::parse(b"B14140").unwrap_err();
+        assert_eq!(error.kind(), Some(ErrorKind::BadTime));
+
+        let error = BRecord::parse(b"B1414065016925N00953112EA021640228").unwrap_err();
+        assert_eq!(error.kind(), Some(ErrorKind::BadAltitude));
+
+        let addition_defs = vec![AdditionDef::new(AdditionCode::FXA, 36, 38)];
+        let error = BRecord::parse_with_additions(b"B1414065016925N00953112EA021640228700", &addition_defs).unwrap_err();
+        assert_eq!(error.to_string(), "missing addition at column 36 (FXA): B1414065016925N00953112EA021640228700");
+    }
+
+    #[test]
+    fn test_to_bytes() {
+        let line = b"B1414065016925S00953112WV-0012-0150";
+        assert_eq!(BRecord::parse(line).unwrap().to_bytes().unwrap(), line.to_vec());
+
+        let addition_defs = vec![
+            AdditionDef::new(AdditionCode::FXA, 36, 38),
+            AdditionDef::new(AdditionCode::SIU, 39, 40),
+        ];
+        let line = b"B1414065016925N00953112EA021640228700309";
+        let record = BRecord::parse_with_additions(line, &addition_defs).unwrap();
+        assert_eq!(record.to_bytes().unwrap(), line[..35].to_vec());
+        assert_eq!(record.to_bytes_with_additions(&addition_defs).unwrap(), line.to_vec());
+        assert!(record.to_bytes_with_additions(&[AdditionDef::new(AdditionCode::ENL, 36, 38)]).is_err());
+    }
+
+    #[test]
+    fn test_format_altitude() {
+        assert_eq!(format_altitude(None).unwrap(), "00000");
+        assert_eq!(format_altitude(Some(0)).unwrap(), "-0000");
+        assert_eq!(format_altitude(Some(2164)).unwrap(), "02164");
+        assert_eq!(format_altitude(Some(40000)).unwrap(), "40000");
+        assert_eq!(format_altitude(Some(99999)).unwrap(), "99999");
+        assert_eq!(format_altitude(Some(123456)), None);
+        assert_eq!(format_altitude(Some(-12)).unwrap(), "-0012");
+        assert_eq!(format_altitude(Some(-9999)).unwrap(), "-9999");
+        assert_eq!(format_altitude(Some(-32768)), None);
+
+        for value in &[None, Some(0), Some(1), Some(-1), Some(99999), Some(-9999)] {
+            assert_eq!(parse_altitude(format_altitude(*value).unwrap().as_bytes()), *value);
+        }
+    }
+
+    #[test]
+    fn test_to_bytes_zero_altitude() {
+        let mut record = BRecord::parse(b"B1414065016925N00953112EA0216402287").unwrap();
+        record.altitude_pressure = Some(0);
+        record.altitude_gps = None;
+        let line = record.to_bytes().unwrap();
+        assert_eq!(BRecord::parse(&line).unwrap(), record);
+
+        // a reader that parses the fields as plain numbers gets zero for both
+        let text = String::from_utf8(line).unwrap();
+        assert_eq!(text[25..30].parse::<i32>(), Ok(0));
+        assert_eq!(text[30..35].parse::<i32>(), Ok(0));
+    }
+
+    #[test]
+    fn test_to_bytes_altitude_out_of_range() {
+        let mut record = BRecord::parse(b"B1414065016925N00953112EA0216402287").unwrap();
+        record.altitude_gps = Some(0);
+        assert_eq!(record.to_bytes().unwrap(), b"B1414065016925N00953112EA02164-0000".to_vec());
+
+        record.altitude_gps = Some(100000);
+        let error = record.to_bytes().unwrap_err();
+        assert_eq!(error.kind(), Some(ErrorKind::BadAltitude));
+        assert_eq!(error.to_string(), "bad altitude at column 31 (gps altitude): B1414065016925N00953112EA02164");
+
+        record.altitude_pressure = Some(-10000);
+        assert_eq!(record.to_bytes().unwrap_err().to_string(), "bad altitude at column 26 (pressure altitude): B1414065016925N00953112EA");
+    }
+
     proptest! {
         #[test]
         #[allow(unused_must_use)]
         fn parse_doesnt_crash(s in r"\PC*") {
             BRecord::parse(s.as_bytes());
         }
+
+        #[test]
+        fn parse_matches_regex(s in r"\PC*|B[0-9]{13}[NSX][0-9]{8}[EWX][AVX][0-9-]{10}\PC{0,5}") {
+            let result = BRecord::parse(s.as_bytes()).map_err(|error| error.to_string());
+            prop_assert_eq!(result, parse_regex(s.as_bytes()).map_err(|error| error.to_string()));
+        }
+
+        #[test]
+        fn to_bytes_roundtrip(s in r"B[0-9]{6}[0-8][0-9][0-5][0-9]{4}[NS][01][0-7][0-9][0-5][0-9]{4}[EW][AV]([0-9]{5}|-[0-9]{4}){2}") {
+            let record = BRecord::parse(s.as_bytes()).unwrap();
+            prop_assert_eq!(record.to_bytes().unwrap(), s.into_bytes());
+        }
     }
 }
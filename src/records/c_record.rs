@@ -1,22 +1,178 @@
-use regex::bytes::Regex;
-
-use ::{Error, Result, Time, Date};
+use ::{Result, Time, Date};
+use ::utils::fields::{Field, check, text_end};
 use ::utils::num::parse_int;
-use ::utils::text::as_text;
-use utils::coordinates::{parse_latitude_unchecked, parse_longitude_unchecked};
+use ::utils::text::{Encoding, text_field};
+use utils::coordinates::{parse_latitude_unchecked, parse_longitude_unchecked, format_latitude, format_longitude};
+use ::prelude::*;
+
+const DECLARATION_FIELDS: &[Field] = &[
+    Field::date("date"),
+    Field::time("time"),
+    Field::date("flight date"),
+    Field::number("task number", 4),
+    Field::number("number of turnpoints", 2),
+];
+
+const TURNPOINT_FIELDS: &[Field] = &[
+    Field::latitude(),
+    Field::longitude(),
+];
+
+/// Task/declaration record, either the initial declaration line or one of
+/// the following turnpoint lines
+#[derive(Debug, PartialEq)]
+pub enum CRecord {
+    Declaration(CRecordDeclaration),
+    Turnpoint(CRecordTurnpoint),
+}
+
+impl CRecord {
+    pub fn parse(line: &[u8]) -> Result<CRecord> {
+        CRecord::parse_encoded(line, None)
+    }
+
+    /// Like `parse()`, but decodes the text with the given `encoding`.
+    pub fn parse_with_encoding(line: &[u8], encoding: Encoding) -> Result<CRecord> {
+        CRecord::parse_encoded(line, Some(encoding))
+    }
+
+    pub(crate) fn parse_encoded(line: &[u8], encoding: Option<Encoding>) -> Result<CRecord> {
+        // declarations have a digit at the position of the latitude hemisphere
+        if line.get(8).is_some_and(|b| *b == b'N' || *b == b'S') {
+            CRecordTurnpoint::parse_encoded(line, encoding).map(CRecord::Turnpoint)
+        } else {
+            CRecordDeclaration::parse_encoded(line, encoding).map(CRecord::Declaration)
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match *self {
+            CRecord::Declaration(ref record) => record.to_bytes(),
+            CRecord::Turnpoint(ref record) => record.to_bytes(),
+        }
+    }
+
+    /// Checks if the line looks like a declaration line (`C` followed by
+    /// date, time, flight date, task number and number of turnpoints).
+    pub fn is_declaration_line(line: &[u8]) -> bool {
+        line.len() >= 25 && line[0] == b'C' && line[1..25].iter().all(u8::is_ascii_digit)
+    }
+
+    /// Checks if the line looks like a turnpoint line (`C` followed by
+    /// latitude and longitude).
+    pub fn is_turnpoint_line(line: &[u8]) -> bool {
+        line.len() >= 18 && line[0] == b'C' && (line[8] == b'N' || line[8] == b'S')
+    }
+}
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct CRecordDeclaration {
-    date: Date,
-    time: Time,
-    flight_date: Option<Date>,
-    task_number: Option<u16>,
-    num_turnpoints: u8,
-    text: Option<String>,
+    pub date: Date,
+    pub time: Time,
+    pub flight_date: Option<Date>,
+    pub task_number: Option<u16>,
+    pub num_turnpoints: u8,
+    pub text: Option<String>,
 }
 
 impl CRecordDeclaration {
     pub fn parse(line: &[u8]) -> Result<CRecordDeclaration> {
+        CRecordDeclaration::parse_encoded(line, None)
+    }
+
+    /// Like `parse()`, but decodes the text with the given `encoding`.
+    pub fn parse_with_encoding(line: &[u8], encoding: Encoding) -> Result<CRecordDeclaration> {
+        CRecordDeclaration::parse_encoded(line, Some(encoding))
+    }
+
+    fn parse_encoded(line: &[u8], encoding: Option<Encoding>) -> Result<CRecordDeclaration> {
+        check(line, b'C', DECLARATION_FIELDS)?;
+
+        let date = Date::parse_unchecked(&line[1..7]);
+        let time = Time::parse_unchecked(&line[7..13]);
+
+        let flight_date = match &line[13..19] {
+            b"000000" => None,
+            bytes => Some(Date::parse_unchecked(bytes)),
+        };
+
+        let task_number = match &line[19..23] {
+            b"0000" => None,
+            bytes => Some(parse_int::<u16>(bytes).unwrap()),
+        };
+
+        let num_turnpoints = parse_int::<u8>(&line[23..25]).unwrap();
+        let text = if text_end(line, 25) == 25 { None } else { Some(text_field(line, 25, line.len(), "text", encoding)?) };
+
+        Ok(CRecordDeclaration { time, date, flight_date, task_number, num_turnpoints, text })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        format!(
+            "C{}{}{}{:04}{:02}{}",
+            self.date.format(),
+            self.time.format(),
+            self.flight_date.map_or_else(|| "000000".to_string(), |date| date.format()),
+            self.task_number.unwrap_or(0),
+            self.num_turnpoints,
+            self.text.as_ref().map_or("", String::as_str),
+        ).into_bytes()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CRecordTurnpoint {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub text: Option<String>,
+}
+
+impl CRecordTurnpoint {
+    pub fn parse(line: &[u8]) -> Result<CRecordTurnpoint> {
+        CRecordTurnpoint::parse_encoded(line, None)
+    }
+
+    /// Like `parse()`, but decodes the text with the given `encoding`.
+    pub fn parse_with_encoding(line: &[u8], encoding: Encoding) -> Result<CRecordTurnpoint> {
+        CRecordTurnpoint::parse_encoded(line, Some(encoding))
+    }
+
+    fn parse_encoded(line: &[u8], encoding: Option<Encoding>) -> Result<CRecordTurnpoint> {
+        check(line, b'C', TURNPOINT_FIELDS)?;
+
+        let latitude = parse_latitude_unchecked(&line[1..9]);
+        let longitude = parse_longitude_unchecked(&line[9..18]);
+        let text = if text_end(line, 18) == 18 { None } else { Some(text_field(line, 18, line.len(), "text", encoding)?) };
+
+        Ok(CRecordTurnpoint { latitude, longitude, text })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        format!(
+            "C{}{}{}",
+            format_latitude(self.latitude),
+            format_longitude(self.longitude),
+            self.text.as_ref().map_or("", String::as_str),
+        ).into_bytes()
+    }
+
+    /// Checks if this is a `0000000N00000000E` placeholder line, which is
+    /// used for turnpoints that were not declared (e.g. takeoff and landing).
+    pub fn is_placeholder(&self) -> bool {
+        self.latitude == 0. && self.longitude == 0.
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use regex::bytes::Regex;
+    use ::utils::fields::diagnose;
+    use super::*;
+
+    /// Original regex based parser, which the fixed-offset parser has to
+    /// behave exactly like
+    fn parse_declaration_regex(line: &[u8]) -> Result<CRecordDeclaration> {
         lazy_static! {
             static ref RE: Regex = Regex::new(r"(?x-u)
                 ^C
@@ -29,7 +185,7 @@ impl CRecordDeclaration {
             ").unwrap();
         }
 
-        let cap = RE.captures(line).ok_or_else(|| Error::invalid_record(line))?;
+        let cap = RE.captures(line).ok_or_else(|| diagnose(line, DECLARATION_FIELDS))?;
 
         let date = Date::parse_unchecked(&cap[1]);
         let time = Time::parse_unchecked(&cap[2]);
@@ -45,21 +201,14 @@ impl CRecordDeclaration {
         };
 
         let num_turnpoints = parse_int::<u8>(&cap[5]).unwrap();
-        let text = if cap[6].is_empty() { None } else { Some(as_text(&cap[6]).ok_or_else(||Error::invalid_record(line))?) };
+        let text = if cap[6].is_empty() { None } else { Some(text_field(line, 25, line.len(), "text", None)?) };
 
         Ok(CRecordDeclaration { time, date, flight_date, task_number, num_turnpoints, text })
     }
-}
 
-#[derive(Debug)]
-pub struct CRecordTurnpoint {
-    latitude: f64,
-    longitude: f64,
-    text: Option<String>,
-}
-
-impl CRecordTurnpoint {
-    pub fn parse(line: &[u8]) -> Result<CRecordTurnpoint> {
+    /// Original regex based parser, which the fixed-offset parser has to
+    /// behave exactly like
+    fn parse_turnpoint_regex(line: &[u8]) -> Result<CRecordTurnpoint> {
         lazy_static! {
             static ref RE: Regex = Regex::new(r"(?x-u)
                 ^C
@@ -69,20 +218,53 @@ impl CRecordTurnpoint {
             ").unwrap();
         }
 
-        let cap = RE.captures(line).ok_or_else(|| Error::invalid_record(line))?;
+        let cap = RE.captures(line).ok_or_else(|| diagnose(line, TURNPOINT_FIELDS))?;
 
         let latitude = parse_latitude_unchecked(&cap[1]);
         let longitude = parse_longitude_unchecked(&cap[2]);
-        let text = if cap[3].is_empty() { None } else { Some(as_text(&cap[3]).ok_or_else(||Error::invalid_record(line))?) };
+        let text = if cap[3].is_empty() { None } else { Some(text_field(line, 18, line.len(), "text", None)?) };
 
         Ok(CRecordTurnpoint { latitude, longitude, text })
     }
-}
 
+    #[test]
+    fn test_parse() {
+        match CRecord::parse(b"C040516072924000000000002Task").unwrap() {
+            CRecord::Declaration(decl) => assert_eq!(decl.num_turnpoints, 2),
+            CRecord::Turnpoint(_) => panic!("expected declaration"),
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        match CRecord::parse(b"C5111359N00101899WTAKEOFF Lasham Clubhouse").unwrap() {
+            CRecord::Declaration(_) => panic!("expected turnpoint"),
+            CRecord::Turnpoint(tp) => assert_eq!(tp.text, Some("TAKEOFF Lasham Clubhouse".into())),
+        }
+
+        match CRecord::parse(b"C0000000N00000000ET").unwrap() {
+            CRecord::Declaration(_) => panic!("expected turnpoint"),
+            CRecord::Turnpoint(tp) => assert_eq!(tp.text, Some("T".into())),
+        }
+
+        assert!(CRecord::parse(b"C").is_err());
+        assert!(CRecord::parse(b"C12345").is_err());
+    }
+
+    #[test]
+    fn test_line_shapes() {
+        assert!(CRecord::is_declaration_line(b"C150709112124150709000114"));
+        assert!(!CRecord::is_turnpoint_line(b"C150709112124150709000114"));
+        assert!(CRecord::is_turnpoint_line(b"C8911359S17901899E"));
+        assert!(!CRecord::is_declaration_line(b"C8911359S17901899E"));
+        assert!(!CRecord::is_declaration_line(b"C"));
+        assert!(!CRecord::is_turnpoint_line(b"C"));
+    }
+
+    proptest! {
+        #[test]
+        #[allow(unused_must_use)]
+        fn parse_doesnt_crash(s in r"\PC*") {
+            CRecord::parse(s.as_bytes());
+        }
+    }
 
     #[test]
     fn test_parse_declaration() {
@@ -117,6 +299,12 @@ mod tests {
         fn parse_declaration_with_random_data(s in r"\PC*") {
             CRecordDeclaration::parse(s.as_bytes());
         }
+
+        #[test]
+        fn parse_declaration_matches_regex(s in r"\PC*|C[0-9]{22}[0-9a]{2}\PC{0,5}(\n\PC{0,3})?") {
+            let result = CRecordDeclaration::parse(s.as_bytes()).map_err(|error| error.to_string());
+            prop_assert_eq!(result, parse_declaration_regex(s.as_bytes()).map_err(|error| error.to_string()));
+        }
     }
 
     #[test]
@@ -155,6 +343,25 @@ mod tests {
         assert_relative_eq!(tp.latitude, -89.18931666666667);
         assert_relative_eq!(tp.longitude, 179.03165);
         assert_eq!(tp.text, None);
+        assert!(!tp.is_placeholder());
+
+        let tp = CRecordTurnpoint::parse(b"C0000000N00000000ET").unwrap();
+        assert!(tp.is_placeholder());
+        assert_eq!(tp.text, Some("T".into()));
+    }
+
+    #[test]
+    fn test_to_bytes() {
+        let lines: &[&[u8]] = &[
+            b"C040516072924000000000002Task",
+            b"C150709112124150709000114",
+            b"C5111359N00101899WTAKEOFF Lasham Clubhouse",
+            b"C8911359S17901899E",
+            b"C0000000N00000000ET",
+        ];
+        for line in lines {
+            assert_eq!(CRecord::parse(line).unwrap().to_bytes(), line.to_vec());
+        }
     }
 
     proptest! {
@@ -163,5 +370,11 @@ mod tests {
         fn parse_turnpoint_with_random_data(s in r"\PC*") {
             CRecordTurnpoint::parse(s.as_bytes());
         }
+
+        #[test]
+        fn parse_turnpoint_matches_regex(s in r"\PC*|C[0-9]{7}[NS][0-9]{8}[EWa]\PC{0,5}(\n\PC{0,3})?") {
+            let result = CRecordTurnpoint::parse(s.as_bytes()).map_err(|error| error.to_string());
+            prop_assert_eq!(result, parse_turnpoint_regex(s.as_bytes()).map_err(|error| error.to_string()));
+        }
     }
 }
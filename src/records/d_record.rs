@@ -1,6 +1,6 @@
-use regex::bytes::Regex;
-
-use ::{Error, Result};
+use ::{ErrorKind, Result};
+use ::utils::fields::{Field, check, text_end};
+use ::prelude::*;
 
 
 #[derive(Debug, PartialEq, Eq)]
@@ -26,8 +26,50 @@ impl GPSQualifier {
     }
 }
 
+const FIELDS: &[Field] = &[
+    Field::new("gps qualifier", 1, ErrorKind::InvalidRecord, is_gps_qualifier),
+];
+
+fn is_gps_qualifier(bytes: &[u8]) -> bool {
+    bytes == b"1" || bytes == b"2"
+}
+
 impl DRecord {
     pub fn parse(line: &[u8]) -> Result<DRecord> {
+        check(line, b'D', FIELDS)?;
+
+        let gps_qualifier = GPSQualifier::from_byte(line[1]).unwrap();
+        let dgps_station_id = if text_end(line, 2) >= 6 {
+            Some(line[2..6].to_vec())
+        } else {
+            None
+        };
+
+        Ok(DRecord { gps_qualifier, dgps_station_id })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut line = match self.gps_qualifier {
+            GPSQualifier::GPS => b"D1".to_vec(),
+            GPSQualifier::DGPS => b"D2".to_vec(),
+        };
+        if let Some(ref station_id) = self.dgps_station_id {
+            line.extend_from_slice(station_id);
+        }
+        line
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use regex::bytes::Regex;
+    use ::utils::fields::diagnose;
+    use super::*;
+
+    /// Original regex based parser, which the fixed-offset parser has to
+    /// behave exactly like
+    fn parse_regex(line: &[u8]) -> Result<DRecord> {
         lazy_static! {
             static ref RE: Regex = Regex::new(r"(?x-u)
                 ^D
@@ -36,19 +78,13 @@ impl DRecord {
             ").unwrap();
         }
 
-        let cap = RE.captures(line).ok_or_else(|| Error::invalid_record(line))?;
+        let cap = RE.captures(line).ok_or_else(|| diagnose(line, FIELDS))?;
 
         let gps_qualifier = GPSQualifier::from_byte(cap[1][0]).unwrap();
         let dgps_station_id = cap.get(2).map(|it| it.as_bytes().to_vec());
 
         Ok(DRecord { gps_qualifier, dgps_station_id })
     }
-}
-
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
     #[test]
     fn test_parse() {
@@ -61,11 +97,23 @@ mod tests {
         assert_eq!(record.dgps_station_id, Some(b"abc3".to_vec()));
     }
 
+    #[test]
+    fn test_to_bytes() {
+        assert_eq!(DRecord::parse(b"D1").unwrap().to_bytes(), b"D1".to_vec());
+        assert_eq!(DRecord::parse(b"D2abc3").unwrap().to_bytes(), b"D2abc3".to_vec());
+    }
+
     proptest! {
         #[test]
         #[allow(unused_must_use)]
         fn parse_doesnt_crash(s in r"\PC*") {
             DRecord::parse(s.as_bytes());
         }
+
+        #[test]
+        fn parse_matches_regex(s in r"\PC*|D[123]\PC{0,5}(\n\PC{0,3})?") {
+            let result = DRecord::parse(s.as_bytes()).map_err(|error| error.to_string());
+            prop_assert_eq!(result, parse_regex(s.as_bytes()).map_err(|error| error.to_string()));
+        }
     }
 }
@@ -1,9 +1,14 @@
-use regex::bytes::Regex;
-
-use ::{Error, Result};
+use ::Result;
 use ::utils::datetime::Time;
 use ::utils::events::EventCode;
-use ::utils::text::as_text;
+use ::utils::fields::{Field, check, text_end};
+use ::utils::text::{Encoding, text_field};
+use ::prelude::*;
+
+const FIELDS: &[Field] = &[
+    Field::time("time"),
+    Field::code("event code"),
+];
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct ERecord {
@@ -14,6 +19,43 @@ pub struct ERecord {
 
 impl ERecord {
     pub fn parse(line: &[u8]) -> Result<ERecord> {
+        ERecord::parse_encoded(line, None)
+    }
+
+    /// Like `parse()`, but decodes the text with the given `encoding`.
+    pub fn parse_with_encoding(line: &[u8], encoding: Encoding) -> Result<ERecord> {
+        ERecord::parse_encoded(line, Some(encoding))
+    }
+
+    pub(crate) fn parse_encoded(line: &[u8], encoding: Option<Encoding>) -> Result<ERecord> {
+        check(line, b'E', FIELDS)?;
+
+        let time = Time::parse_unchecked(&line[1..7]);
+        let code = unsafe { EventCode::from_bytes_unchecked(&line[7..10]) };
+        let text = match text_end(line, 10) {
+            10 => None,
+            end => Some(text_field(line, 10, end, "text", encoding)?),
+        };
+
+        Ok(ERecord { time, code, text })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let text = self.text.as_ref().map_or("", String::as_str);
+        format!("E{}{}{}", self.time.format(), self.code.as_str(), text).into_bytes()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use regex::bytes::Regex;
+    use ::utils::fields::diagnose;
+    use super::*;
+
+    /// Original regex based parser, which the fixed-offset parser has to
+    /// behave exactly like
+    fn parse_regex(line: &[u8]) -> Result<ERecord> {
         lazy_static! {
             static ref RE: Regex = Regex::new(r"(?x-u)
                 ^E
@@ -23,23 +65,17 @@ impl ERecord {
             ").unwrap();
         }
 
-        let cap = RE.captures(line).ok_or_else(|| Error::invalid_record(line))?;
+        let cap = RE.captures(line).ok_or_else(|| diagnose(line, FIELDS))?;
 
         let time = Time::parse_unchecked(&cap[1]);
         let code = unsafe { EventCode::from_bytes_unchecked(&cap[2]) };
         let text = match cap.get(3) {
             None => None,
-            Some(m) => Some(as_text(m.as_bytes()).ok_or_else(|| Error::invalid_record(line))?),
+            Some(m) => Some(text_field(line, m.start(), m.end(), "text", None)?),
         };
 
         Ok(ERecord { time, code, text })
     }
-}
-
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
     #[test]
     fn test_parse() {
@@ -54,11 +90,24 @@ mod tests {
         assert_eq!(record.text, Some("102312".into()));
     }
 
+    #[test]
+    fn test_to_bytes() {
+        assert_eq!(ERecord::parse(b"E104533PEV").unwrap().to_bytes(), b"E104533PEV".to_vec());
+        assert_eq!(ERecord::parse(b"E104544ATS102312").unwrap().to_bytes(), b"E104544ATS102312".to_vec());
+        assert_eq!(ERecord::parse(b"E104544XYZfoo").unwrap().to_bytes(), b"E104544XYZfoo".to_vec());
+    }
+
     proptest! {
         #[test]
         #[allow(unused_must_use)]
         fn parse_doesnt_crash(s in r"\PC*") {
             ERecord::parse(s.as_bytes());
         }
+
+        #[test]
+        fn parse_matches_regex(s in r"\PC*|E[0-9]{6}[A-Z0-9a]{3}\PC{0,5}(\n\PC{0,3})?") {
+            let result = ERecord::parse(s.as_bytes()).map_err(|error| error.to_string());
+            prop_assert_eq!(result, parse_regex(s.as_bytes()).map_err(|error| error.to_string()));
+        }
     }
 }
@@ -1,8 +1,9 @@
 use std::str::from_utf8_unchecked;
-use regex::bytes::Regex;
 
 use ::utils::datetime::Time;
-use ::{Error, Result};
+use ::Result;
+use ::utils::fields::{Field, check, is_code};
+use ::prelude::*;
 
 
 #[derive(Debug, PartialEq, Eq)]
@@ -13,6 +14,37 @@ pub struct FRecord {
 
 impl FRecord {
     pub fn parse(line: &[u8]) -> Result<FRecord> {
+        const FIELDS: &[Field] = &[
+            Field::time("time"),
+        ];
+
+        check(line, b'F', FIELDS)?;
+
+        let time = Time::parse_unchecked(&line[1..7]);
+        let satellite_ids: Vec<_> = line[7..]
+            .chunks_exact(2)
+            .take_while(|bytes| is_code(bytes))
+            .map(|bytes| unsafe { from_utf8_unchecked(bytes) }.into())
+            .collect();
+
+        Ok(FRecord { time, satellite_ids })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        format!("F{}{}", self.time.format(), self.satellite_ids.concat()).into_bytes()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use regex::bytes::Regex;
+    use ::utils::fields::diagnose;
+    use super::*;
+
+    /// Original regex based parser, which the fixed-offset parser has to
+    /// behave exactly like
+    fn parse_regex(line: &[u8]) -> Result<FRecord> {
         lazy_static! {
             static ref RE: Regex = Regex::new(r"(?x-u)
                 ^F
@@ -21,7 +53,11 @@ impl FRecord {
             ").unwrap();
         }
 
-        let cap = RE.captures(line).ok_or_else(|| Error::invalid_record(line))?;
+        const FIELDS: &[Field] = &[
+            Field::time("time"),
+        ];
+
+        let cap = RE.captures(line).ok_or_else(|| diagnose(line, FIELDS))?;
 
         let time = Time::parse_unchecked(&cap[1]);
         let satellite_ids: Vec<_> = cap[2]
@@ -31,12 +67,6 @@ impl FRecord {
 
         Ok(FRecord { time, satellite_ids })
     }
-}
-
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
     #[test]
     fn test_parse() {
@@ -49,11 +79,23 @@ mod tests {
         assert_eq!(record.satellite_ids, Vec::<String>::new());
     }
 
+    #[test]
+    fn test_to_bytes() {
+        assert_eq!(FRecord::parse(b"F1234560102ABC342").unwrap().to_bytes(), b"F1234560102ABC342".to_vec());
+        assert_eq!(FRecord::parse(b"F123456").unwrap().to_bytes(), b"F123456".to_vec());
+    }
+
     proptest! {
         #[test]
         #[allow(unused_must_use)]
         fn parse_doesnt_crash(s in r"\PC*") {
             FRecord::parse(s.as_bytes());
         }
+
+        #[test]
+        fn parse_matches_regex(s in r"\PC*|F[0-9]{6}[A-Z0-9a]{0,15}") {
+            let result = FRecord::parse(s.as_bytes()).map_err(|error| error.to_string());
+            prop_assert_eq!(result, parse_regex(s.as_bytes()).map_err(|error| error.to_string()));
+        }
     }
 }
@@ -1,7 +1,11 @@
-use regex::bytes::Regex;
+use ::{ErrorKind, Result};
+use ::utils::fields::{Field, diagnose};
+use ::utils::text::{Encoding, text_field};
+use ::prelude::*;
 
-use ::{Error, Result};
-use ::utils::text::as_text;
+const FIELDS: &[Field] = &[
+    Field::new("text", 1, ErrorKind::InvalidRecord, |_| true),
+];
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct GRecord {
@@ -10,26 +14,55 @@ pub struct GRecord {
 
 impl GRecord {
     pub fn parse(line: &[u8]) -> Result<GRecord> {
-        lazy_static! {
-            static ref RE: Regex = Regex::new(r"(?x-u)
-                ^G
-                (.+)              # text
-            ").unwrap();
-        }
+        GRecord::parse_encoded(line, None)
+    }
+
+    /// Like `parse()`, but decodes the text with the given `encoding`.
+    pub fn parse_with_encoding(line: &[u8], encoding: Encoding) -> Result<GRecord> {
+        GRecord::parse_encoded(line, Some(encoding))
+    }
 
-        let cap = RE.captures(line).ok_or_else(|| Error::invalid_record(line))?;
+    pub(crate) fn parse_encoded(line: &[u8], encoding: Option<Encoding>) -> Result<GRecord> {
+        // the text has to start with something else than a line feed
+        if line.len() < 2 || line[0] != b'G' || line[1] == b'\n' {
+            return Err(diagnose(line, FIELDS));
+        }
 
-        let text = as_text(&cap[1]).ok_or_else(|| Error::invalid_record(line))?;
+        let text = text_field(line, 1, line.len(), "text", encoding)?;
 
         Ok(GRecord { text })
     }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        format!("G{}", self.text).into_bytes()
+    }
 }
 
 
 #[cfg(test)]
 mod tests {
+    use regex::bytes::Regex;
     use super::*;
 
+    /// Original regex based parser, which the hand-written parser has to
+    /// behave exactly like
+    fn parse_regex(line: &[u8]) -> Result<GRecord> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"(?x-u)
+                ^G
+                (.+)              # text
+            ").unwrap();
+        }
+
+        if !RE.is_match(line) {
+            return Err(diagnose(line, FIELDS));
+        }
+
+        let text = text_field(line, 1, line.len(), "text", None)?;
+
+        Ok(GRecord { text })
+    }
+
     #[test]
     fn test_parse() {
         let record = GRecord::parse(b"G0AD873C2B7B20B7BECBFF52F1581601F7AAE3769").unwrap();
@@ -38,11 +71,23 @@ mod tests {
         assert!(GRecord::parse(b"G").is_err());
     }
 
+    #[test]
+    fn test_to_bytes() {
+        let line = b"G0AD873C2B7B20B7BECBFF52F1581601F7AAE3769";
+        assert_eq!(GRecord::parse(line).unwrap().to_bytes(), line.to_vec());
+    }
+
     proptest! {
         #[test]
         #[allow(unused_must_use)]
         fn parse_doesnt_crash(s in r"\PC*") {
             GRecord::parse(s.as_bytes());
         }
+
+        #[test]
+        fn parse_matches_regex(s in r"\PC*|G\n?\PC{0,3}(\n\PC{0,3})?") {
+            let result = GRecord::parse(s.as_bytes()).map_err(|error| error.to_string());
+            prop_assert_eq!(result, parse_regex(s.as_bytes()).map_err(|error| error.to_string()));
+        }
     }
 }
@@ -1,8 +1,13 @@
-use regex::bytes::Regex;
+use ::{ErrorKind, Result};
+use ::utils::fields::{Field, check, is_code, text_end};
+use ::utils::headers::{HeaderSource, HeaderCode, HeaderValue};
+use ::utils::text::{Encoding, text_field};
+use ::prelude::*;
 
-use ::{Error, Result};
-use ::utils::headers::{HeaderSource, HeaderCode};
-use ::utils::text::as_text;
+const FIELDS: &[Field] = &[
+    Field::new("data source", 1, ErrorKind::InvalidRecord, |bytes| b"FOP".contains(&bytes[0])),
+    Field::new("subtype", 3, ErrorKind::InvalidRecord, is_code),
+];
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct HRecord {
@@ -17,6 +22,59 @@ impl HRecord {
     }
 
     pub fn parse(line: &[u8]) -> Result<HRecord> {
+        HRecord::parse_encoded(line, None)
+    }
+
+    /// Like `parse()`, but decodes the text with the given `encoding`.
+    pub fn parse_with_encoding(line: &[u8], encoding: Encoding) -> Result<HRecord> {
+        HRecord::parse_encoded(line, Some(encoding))
+    }
+
+    pub(crate) fn parse_encoded(line: &[u8], encoding: Option<Encoding>) -> Result<HRecord> {
+        check(line, b'H', FIELDS)?;
+
+        let source = HeaderSource::from_byte_unchecked(line[1]);
+        let code = unsafe { HeaderCode::from_bytes_unchecked(&line[2..5]) };
+
+        // the text follows the first colon, which can be preceded by the
+        // long name of the subtype, or directly follows the subtype
+        let start = line[5..].iter().position(|b| *b == b':').map_or(5, |colon| 5 + colon + 1);
+        let text = text_field(line, start, text_end(line, start), "text", encoding)?;
+
+        Ok(HRecord { source, code, text })
+    }
+
+    /// Formats the header as `H`, source, code, long name (if known), colon
+    /// and text.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        format!(
+            "H{}{}{}:{}",
+            self.source.to_char(),
+            self.code.as_str(),
+            self.code.long_name().unwrap_or(""),
+            self.text,
+        ).into_bytes()
+    }
+
+    /// Typed value of the header, decoded depending on the header code
+    pub fn value(&self) -> HeaderValue {
+        HeaderValue::parse(&self.code, &self.text)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use regex::bytes::Regex;
+    use ::utils::fields::diagnose;
+    use super::*;
+    use super::HeaderSource::*;
+    use super::HeaderCode::*;
+    use ::utils::headers::{UtcOffset, GnssAltitudeReference};
+
+    /// Original regex based parser, which the hand-written parser has to
+    /// behave exactly like
+    fn parse_regex(line: &[u8]) -> Result<HRecord> {
         lazy_static! {
             static ref RE: Regex = Regex::new(r"(?x-u)
                 ^H
@@ -27,24 +85,16 @@ impl HRecord {
             ").unwrap();
         }
 
-        let cap = RE.captures(line).ok_or_else(|| Error::invalid_record(line))?;
+        let cap = RE.captures(line).ok_or_else(|| diagnose(line, FIELDS))?;
 
         let source = HeaderSource::from_byte_unchecked(cap[1][0]);
         let code = unsafe { HeaderCode::from_bytes_unchecked(&cap[2]) };
 
-        let text = as_text(&cap[3])
-            .ok_or_else(||Error::invalid_record(line))?;
+        let text = cap.get(3).unwrap();
+        let text = text_field(line, text.start(), text.end(), "text", None)?;
 
         Ok(HRecord { source, code, text })
     }
-}
-
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use super::HeaderSource::*;
-    use super::HeaderCode::*;
 
     fn assert_header(bytes: &[u8], code: HeaderCode, text: &str, source: HeaderSource) {
         assert_eq!(HRecord::parse(bytes).unwrap(), HRecord::new(source, code, text));
@@ -116,11 +166,54 @@ mod tests {
         assert_header(b"HFCIDCompetitionID:TH", CID, "TH", FlightRecorder);
     }
 
+    #[test]
+    fn test_value() {
+        let record = HRecord::parse(b"HFDTEDATE:040516,01").unwrap();
+        assert_eq!(record.value(), HeaderValue::Date { date: ::Date::new(16, 5, 4), flight_number: Some(1) });
+
+        let record = HRecord::parse(b"HFDTE040516").unwrap();
+        assert_eq!(record.value(), HeaderValue::Date { date: ::Date::new(16, 5, 4), flight_number: None });
+
+        let record = HRecord::parse(b"HFFXA500").unwrap();
+        assert_eq!(record.value(), HeaderValue::FixAccuracy(500));
+
+        let record = HRecord::parse(b"HFTZNTIMEZONE:+2.00").unwrap();
+        assert_eq!(record.code, TZN);
+        assert_eq!(record.value(), HeaderValue::TimeZone(UtcOffset::from_minutes(120)));
+
+        let record = HRecord::parse(b"HFALGALTGPS:GEO").unwrap();
+        assert_eq!(record.value(), HeaderValue::GnssAltitude(GnssAltitudeReference::Geoid));
+
+        let record = HRecord::parse(b"HFPLTPILOT:KEVIN.HOULIHAN").unwrap();
+        assert_eq!(record.value(), HeaderValue::Text("KEVIN.HOULIHAN".into()));
+    }
+
+    #[test]
+    fn test_to_bytes() {
+        assert_eq!(HRecord::new(FlightRecorder, DTE, "040516,01").to_bytes(), b"HFDTEDATE:040516,01".to_vec());
+        assert_eq!(HRecord::new(FlightRecorder, PLT, "John Doe").to_bytes(), b"HFPLTPILOTINCHARGE:John Doe".to_vec());
+        assert_eq!(HRecord::new(Observer, FXA, "500").to_bytes(), b"HOFXA:500".to_vec());
+        assert_eq!(HRecord::new(FlightRecorder, GPS, "uBLOX:LEA-4P").to_bytes(), b"HFGPSRECEIVER:uBLOX:LEA-4P".to_vec());
+        assert_eq!(HRecord::new(FlightRecorder, "XYZ".parse().unwrap(), ":x").to_bytes(), b"HFXYZ::x".to_vec());
+    }
+
     proptest! {
         #[test]
         #[allow(unused_must_use)]
         fn parse_doesnt_crash(s in r"\PC*") {
             HRecord::parse(s.as_bytes());
         }
+
+        #[test]
+        fn parse_matches_regex(s in r"\PC*|H[FOPa][A-Z0-9a]{3}[A-Za :\n]{0,5}\PC{0,3}(\n\PC{0,3})?") {
+            let result = HRecord::parse(s.as_bytes()).map_err(|error| error.to_string());
+            prop_assert_eq!(result, parse_regex(s.as_bytes()).map_err(|error| error.to_string()));
+        }
+
+        #[test]
+        fn to_bytes_roundtrip(source in "[FO]", code in "[A-Z]{3}", text in r"\PC*") {
+            let record = HRecord::new(HeaderSource::from_byte_unchecked(source.as_bytes()[0]), code.parse().unwrap(), text);
+            prop_assert_eq!(HRecord::parse(&record.to_bytes()).unwrap(), record);
+        }
     }
 }
@@ -1,8 +1,8 @@
-use regex::bytes::Regex;
-
-use ::{Error, Result};
+use ::{Error, ErrorKind, Result};
+use ::utils::fields::{Field, check, diagnose, is_digits};
 use ::utils::num::parse_int;
 use ::utils::additions::AdditionDef;
+use ::prelude::*;
 
 
 // Examples:
@@ -24,6 +24,89 @@ pub struct IRecord {
 
 impl IRecord {
     pub fn parse(line: &[u8]) -> Result<IRecord> {
+        let (num_additions, addition_defs) = parse_addition_defs(line, b'I')?;
+        Ok(IRecord { num_additions, addition_defs })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut line = format!("I{:02}", self.num_additions);
+        for def in &self.addition_defs {
+            line.push_str(&format!("{:02}{:02}{}", def.start_byte, def.end_byte, def.code.as_str()));
+        }
+        line.into_bytes()
+    }
+}
+
+
+/// Parses the number of additions and the addition definitions of an I or J
+/// record. Anything after the last valid definition is ignored.
+pub(crate) fn parse_addition_defs(line: &[u8], record_type: u8) -> Result<(u8, Vec<AdditionDef>)> {
+    const FIELDS: &[Field] = &[
+        Field::number("number of additions", 2),
+    ];
+
+    check(line, record_type, FIELDS).map_err(|_| diagnose_addition_defs(line))?;
+
+    let addition_defs: Vec<_> = line[3..].chunks_exact(7)
+        .take_while(|bytes| is_addition_def(bytes))
+        .map(|bytes| unsafe { AdditionDef::parse_unchecked(bytes) })
+        .collect();
+
+    if addition_defs.is_empty() {
+        return Err(diagnose_addition_defs(line));
+    }
+
+    check_addition_ranges(line, &addition_defs)?;
+
+    Ok((parse_int(&line[1..3]).unwrap(), addition_defs))
+}
+
+/// Checks that every definition describes a byte range that B and K record
+/// additions can be sliced with.
+pub(crate) fn check_addition_ranges(line: &[u8], addition_defs: &[AdditionDef]) -> Result<()> {
+    match addition_defs.iter().position(|def| !def.is_valid_range()) {
+        Some(i) => Err(Error::parse(ErrorKind::AdditionOutOfRange, line)
+            .in_field(4 + 7 * i, addition_defs[i].code.as_str())),
+        None => Ok(()),
+    }
+}
+
+fn is_addition_def(bytes: &[u8]) -> bool {
+    is_digits(&bytes[..4]) && bytes[4..].iter().all(u8::is_ascii_alphanumeric)
+}
+
+/// Diagnoses an I or J record line that could not be parsed
+pub(crate) fn diagnose_addition_defs(line: &[u8]) -> Error {
+    const FIELDS: &[Field] = &[
+        Field::number("number of additions", 2),
+    ];
+
+    let error = diagnose(line, FIELDS);
+    if error.kind() != Some(ErrorKind::InvalidRecord) {
+        return error;
+    }
+
+    // the record ends before all of the announced definitions, or a
+    // definition is malformed
+    let num_additions: usize = parse_int(&line[1..3]).unwrap();
+    let num_defs = line[3..].chunks_exact(7).take_while(|bytes| is_addition_def(bytes)).count();
+    let rest = &line[3 + 7 * num_defs..];
+    if num_defs < num_additions && rest.len() < 7 {
+        Error::parse(ErrorKind::AdditionCountMismatch, line).in_field(4 + 7 * num_defs, "addition definitions")
+    } else {
+        Error::invalid_record(line).in_field(4 + 7 * num_defs, "addition definition")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::bytes::Regex;
+    use super::*;
+    use ::utils::additions::AdditionCode;
+
+    /// Original regex based parser, which the fixed-offset parser has to
+    /// behave exactly like
+    fn parse_regex(line: &[u8]) -> Result<IRecord> {
         lazy_static! {
             static ref RE: Regex = Regex::new(r"(?x-u)
                 ^I                          # record typ
@@ -32,7 +115,7 @@ impl IRecord {
             ").unwrap();
         }
 
-        let cap = RE.captures(line).ok_or_else(|| Error::invalid_record(line))?;
+        let cap = RE.captures(line).ok_or_else(|| diagnose_addition_defs(line))?;
 
         let num_additions = parse_int(&cap[1]).unwrap();
 
@@ -40,15 +123,10 @@ impl IRecord {
             .map(|bytes| unsafe { AdditionDef::parse_unchecked(bytes) })
             .collect();
 
+        check_addition_ranges(line, &addition_defs)?;
+
         Ok(IRecord { num_additions, addition_defs })
     }
-}
-
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use ::utils::additions::AdditionCode;
 
     #[test]
     fn test_example_1() {
@@ -69,11 +147,43 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn test_to_bytes() {
+        assert_eq!(IRecord::parse(b"I023638FXA3941ENL").unwrap().to_bytes(), b"I023638FXA3941ENL".to_vec());
+        assert_eq!(IRecord::parse(b"I013638XYZ").unwrap().to_bytes(), b"I013638XYZ".to_vec());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(IRecord::parse(b"I0a3638FXA").unwrap_err().kind(), Some(ErrorKind::BadNumber));
+        assert_eq!(IRecord::parse(b"I02").unwrap_err().kind(), Some(ErrorKind::AdditionCountMismatch));
+        assert_eq!(IRecord::parse(b"I01363").unwrap_err().kind(), Some(ErrorKind::AdditionCountMismatch));
+        assert_eq!(IRecord::parse(b"I13").unwrap_err().kind(), Some(ErrorKind::AdditionCountMismatch));
+        assert_eq!(IRecord::parse(b"I00").unwrap_err().kind(), Some(ErrorKind::InvalidRecord));
+        assert_eq!(IRecord::parse(b"I003638").unwrap_err().kind(), Some(ErrorKind::InvalidRecord));
+        assert_eq!(IRecord::parse(b"I01ab38FXA").unwrap_err().kind(), Some(ErrorKind::InvalidRecord));
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let error = IRecord::parse(b"I010003FXA").unwrap_err();
+        assert_eq!(error.to_string(), "addition out of range at column 4 (FXA): I010003FXA");
+        assert_eq!(IRecord::parse(b"I013836FXA").unwrap_err().kind(), Some(ErrorKind::AdditionOutOfRange));
+        assert_eq!(IRecord::parse(b"I023638FXA3939ENL").unwrap().addition_defs.len(), 2);
+        assert_eq!(IRecord::parse(b"I023638FXA4139ENL").unwrap_err().kind(), Some(ErrorKind::AdditionOutOfRange));
+    }
+
     proptest! {
         #[test]
         #[allow(unused_must_use)]
         fn parse_doesnt_crash(s in r"\PC*") {
             IRecord::parse(s.as_bytes());
         }
+
+        #[test]
+        fn parse_matches_regex(s in r"\PC*|I[0-9a]{2}([0-9a]{4}[A-Za-z0-9_]{3}){0,3}\PC{0,8}") {
+            let result = IRecord::parse(s.as_bytes()).map_err(|error| error.to_string());
+            prop_assert_eq!(result, parse_regex(s.as_bytes()).map_err(|error| error.to_string()));
+        }
     }
 }
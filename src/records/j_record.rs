@@ -1,8 +1,7 @@
-use regex::bytes::Regex;
-
-use ::{Error, Result};
-use ::utils::num::parse_int;
+use ::Result;
 use ::utils::additions::AdditionDef;
+use super::i_record::parse_addition_defs;
+use ::prelude::*;
 
 
 #[derive(Debug, PartialEq, Eq)]
@@ -19,6 +18,31 @@ pub struct JRecord {
 
 impl JRecord {
     pub fn parse(line: &[u8]) -> Result<JRecord> {
+        let (num_additions, addition_defs) = parse_addition_defs(line, b'J')?;
+        Ok(JRecord { num_additions, addition_defs })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut line = format!("J{:02}", self.num_additions);
+        for def in &self.addition_defs {
+            line.push_str(&format!("{:02}{:02}{}", def.start_byte, def.end_byte, def.code.as_str()));
+        }
+        line.into_bytes()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use regex::bytes::Regex;
+    use super::*;
+    use super::super::i_record::{check_addition_ranges, diagnose_addition_defs};
+    use ::utils::additions::AdditionCode;
+    use ::utils::num::parse_int;
+
+    /// Original regex based parser, which the fixed-offset parser has to
+    /// behave exactly like
+    fn parse_regex(line: &[u8]) -> Result<JRecord> {
         lazy_static! {
             static ref RE: Regex = Regex::new(r"(?x-u)
                 ^J
@@ -27,7 +51,7 @@ impl JRecord {
             ").unwrap();
         }
 
-        let cap = RE.captures(line).ok_or_else(|| Error::invalid_record(line))?;
+        let cap = RE.captures(line).ok_or_else(|| diagnose_addition_defs(line))?;
 
         let num_additions = parse_int(&cap[1]).unwrap();
 
@@ -35,15 +59,10 @@ impl JRecord {
             .map(|bytes| unsafe { AdditionDef::parse_unchecked(bytes) })
             .collect();
 
+        check_addition_ranges(line, &addition_defs)?;
+
         Ok(JRecord { num_additions, addition_defs })
     }
-}
-
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use ::utils::additions::AdditionCode;
 
     #[test]
     fn test_parse() {
@@ -54,11 +73,22 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn test_to_bytes() {
+        assert_eq!(JRecord::parse(b"J010810HDT").unwrap().to_bytes(), b"J010810HDT".to_vec());
+    }
+
     proptest! {
         #[test]
         #[allow(unused_must_use)]
         fn parse_doesnt_crash(s in r"\PC*") {
             JRecord::parse(s.as_bytes());
         }
+
+        #[test]
+        fn parse_matches_regex(s in r"\PC*|J[0-9a]{2}([0-9a]{4}[A-Za-z0-9_]{3}){0,3}\PC{0,8}") {
+            let result = JRecord::parse(s.as_bytes()).map_err(|error| error.to_string());
+            prop_assert_eq!(result, parse_regex(s.as_bytes()).map_err(|error| error.to_string()));
+        }
     }
 }
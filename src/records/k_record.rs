@@ -1,34 +1,66 @@
-use regex::bytes::Regex;
-
-use ::{Error, Result, Time};
+use ::{Result, Time};
+use ::utils::fields::{Field, check};
 use ::utils::additions::*;
+use ::prelude::*;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct KRecord {
     pub time: Time,
     pub additions: AdditionsMap,
 }
 
+/// Borrowed variant of `KRecord`, which does not allocate and only decodes
+/// the additions when they are accessed
+#[derive(Debug, Clone, Copy)]
+pub struct KRecordRef<'a> {
+    pub time: Time,
+    pub additions: AdditionsRef<'a>,
+}
+
 impl KRecord {
     pub fn parse(line: &[u8]) -> Result<KRecord> {
-        Self::parse_with_additions(line, &vec![])
+        Self::parse_with_additions(line, &[])
     }
 
-    pub fn parse_with_additions(line: &[u8], addition_defs: &Vec<AdditionDef>) -> Result<KRecord> {
-        lazy_static! {
-            static ref RE: Regex = Regex::new(r"(?x-u)
-                ^K
-                (\d{6})                # UTC time
-                (.*)                   # additions
-            ").unwrap();
-        }
+    pub fn parse_with_additions(line: &[u8], addition_defs: &[AdditionDef]) -> Result<KRecord> {
+        KRecordRef::parse_with_additions(line, addition_defs).map(|record| record.to_record())
+    }
+
+    /// Formats the record without any additions.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        format!("K{}", self.time.format()).into_bytes()
+    }
+
+    /// Formats the record with its additions at the byte positions of the
+    /// given J record addition definitions.
+    pub fn to_bytes_with_additions(&self, addition_defs: &[AdditionDef]) -> Result<Vec<u8>> {
+        let mut line = self.to_bytes();
+        addition_defs.format(&mut line, &self.additions)?;
+        Ok(line)
+    }
+}
+
+impl<'a> KRecordRef<'a> {
+    pub fn parse(line: &'a [u8]) -> Result<KRecordRef<'a>> {
+        Self::parse_with_additions(line, &[])
+    }
 
-        let cap = RE.captures(line).ok_or_else(|| Error::invalid_record(line))?;
+    pub fn parse_with_additions(line: &'a [u8], addition_defs: &'a [AdditionDef]) -> Result<KRecordRef<'a>> {
+        const FIELDS: &[Field] = &[
+            Field::time("time"),
+        ];
+
+        check(line, b'K', FIELDS)?;
 
-        let time = Time::parse_unchecked(&cap[1]);
-        let additions = addition_defs.parse(&line)?;
+        let time = Time::parse_unchecked(&line[1..7]);
+        let additions = AdditionsRef::new(line, addition_defs)?;
+
+        Ok(KRecordRef { time, additions })
+    }
 
-        Ok(KRecord { time, additions })
+    /// Copies the record into an owned `KRecord`.
+    pub fn to_record(&self) -> KRecord {
+        KRecord { time: self.time, additions: self.additions.to_map() }
     }
 }
 
@@ -38,10 +70,39 @@ impl AdditionSupport for KRecord {
     }
 }
 
+impl<'a> AdditionSupport for KRecordRef<'a> {
+    fn get_addition(&self, code: &AdditionCode) -> Option<&[u8]> {
+        self.additions.get(code)
+    }
+}
+
 
 #[cfg(test)]
 mod tests {
+    use regex::bytes::Regex;
+    use ::utils::fields::diagnose;
     use super::*;
+    use ::ErrorKind;
+
+    /// Original regex based parser, which the fixed-offset parser has to
+    /// behave exactly like
+    fn parse_regex(line: &[u8]) -> Result<KRecord> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"(?x-u)
+                ^K
+                (\d{6})                # UTC time
+                (.*)                   # additions
+            ").unwrap();
+        }
+
+        const FIELDS: &[Field] = &[
+            Field::time("time"),
+        ];
+
+        let cap = RE.captures(line).ok_or_else(|| diagnose(line, FIELDS))?;
+
+        Ok(KRecord { time: Time::parse_unchecked(&cap[1]), additions: AdditionsMap::new() })
+    }
 
     #[test]
     fn test_parse() {
@@ -54,6 +115,31 @@ mod tests {
         assert_eq!(record.additions.get(&AdditionCode::HDT).unwrap(), b"090");
     }
 
+    #[test]
+    fn test_to_bytes() {
+        let addition_defs = vec![
+            AdditionDef::new(AdditionCode::HDT, 8, 10),
+        ];
+        let record = KRecord::parse_with_additions(b"K160310090", &addition_defs).unwrap();
+        assert_eq!(record.to_bytes(), b"K160310".to_vec());
+        assert_eq!(record.to_bytes_with_additions(&addition_defs).unwrap(), b"K160310090".to_vec());
+    }
+
+    #[test]
+    fn test_borrowed() {
+        let addition_defs = vec![
+            AdditionDef::new(AdditionCode::HDT, 8, 10),
+        ];
+        let record = KRecordRef::parse_with_additions(b"K160310090", &addition_defs).unwrap();
+        assert_eq!(record.time, Time::from_hms(16, 3, 10));
+        assert_eq!(record.heading(), Some(90));
+        assert_eq!(record.to_record().additions.get(&AdditionCode::HDT).unwrap(), b"090");
+
+        let addition_defs = [AdditionDef::new(AdditionCode::HDT, 10, 8)];
+        let error = KRecordRef::parse_with_additions(b"K160310090", &addition_defs).unwrap_err();
+        assert_eq!(error.kind(), Some(ErrorKind::AdditionOutOfRange));
+    }
+
     #[test]
     fn test_headings() {
         let addition_defs = vec![
@@ -79,5 +165,11 @@ mod tests {
         fn parse_doesnt_crash(s in r"\PC*") {
             KRecord::parse(s.as_bytes());
         }
+
+        #[test]
+        fn parse_matches_regex(s in r"\PC*|K[0-9a]{6}\PC{0,5}") {
+            let result = KRecord::parse(s.as_bytes()).map_err(|error| error.to_string());
+            prop_assert_eq!(result, parse_regex(s.as_bytes()).map_err(|error| error.to_string()));
+        }
     }
 }
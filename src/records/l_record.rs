@@ -1,11 +1,16 @@
 use std::str::{FromStr, from_utf8_unchecked};
 
-use regex::bytes::Regex;
+use ::{ErrorKind, Result};
+use ::utils::fields::{Field, diagnose};
+use ::utils::text::{Encoding, text_field};
+use ::prelude::*;
 
-use ::{Error, Result};
-use ::utils::text::as_text;
+const FIELDS: &[Field] = &[
+    Field::new("source", 3, ErrorKind::InvalidRecord, is_source),
+    Field::new("text", 1, ErrorKind::InvalidRecord, |_| true),
+];
 
-#[derive(Debug, PartialEq, Eq, EnumString)]
+#[derive(Debug, PartialEq, Eq, EnumString, AsRefStr)]
 pub enum LRecordSource {
     /// Pilot input
     PLT,
@@ -14,16 +19,26 @@ pub enum LRecordSource {
     /// Official observer input
     OOI,
     /// Other input (e.g. manufacturer)
-    #[strum(default="true")]
+    #[strum(default)]
     Other(String),
 }
 
 impl LRecordSource {
+    /// # Safety
+    ///
+    /// `bytes` must only contain ASCII characters.
     pub unsafe fn from_bytes_unchecked(bytes: &[u8]) -> LRecordSource {
         debug_assert!(bytes.is_ascii());
         let code = from_utf8_unchecked(bytes);
         LRecordSource::from_str(code).unwrap()
     }
+
+    pub fn as_str(&self) -> &str {
+        match *self {
+            LRecordSource::Other(ref code) => code,
+            ref code => code.as_ref(),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -34,27 +49,61 @@ pub struct LRecord {
 
 impl LRecord {
     pub fn parse(line: &[u8]) -> Result<LRecord> {
+        LRecord::parse_encoded(line, None)
+    }
+
+    /// Like `parse()`, but decodes the text with the given `encoding`.
+    pub fn parse_with_encoding(line: &[u8], encoding: Encoding) -> Result<LRecord> {
+        LRecord::parse_encoded(line, Some(encoding))
+    }
+
+    pub(crate) fn parse_encoded(line: &[u8], encoding: Option<Encoding>) -> Result<LRecord> {
+        // the text has to start with something else than a line feed
+        if line.len() < 5 || line[0] != b'L' || !is_source(&line[1..4]) || line[4] == b'\n' {
+            return Err(diagnose(line, FIELDS));
+        }
+
+        let source = unsafe { LRecordSource::from_bytes_unchecked(&line[1..4]) };
+        let text = text_field(line, 4, line.len(), "text", encoding)?;
+
+        Ok(LRecord { source, text })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        format!("L{}{}", self.source.as_str(), self.text).into_bytes()
+    }
+}
+
+/// Converters like SeeYou write sources like `LCU::`, so colons are allowed
+/// in addition to the usual code characters.
+fn is_source(bytes: &[u8]) -> bool {
+    bytes.iter().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || *b == b':')
+}
+
+
+#[cfg(test)]
+mod tests {
+    use regex::bytes::Regex;
+    use super::*;
+
+    /// Original regex based parser, which the hand-written parser has to
+    /// behave exactly like
+    fn parse_regex(line: &[u8]) -> Result<LRecord> {
         lazy_static! {
             static ref RE: Regex = Regex::new(r"(?x-u)
                 ^L
-                ([A-Z0-9]{3})     # source
+                ([A-Z0-9:]{3})    # source (converters like SeeYou write `LCU::`)
                 (.+)              # text
             ").unwrap();
         }
 
-        let cap = RE.captures(line).ok_or_else(|| Error::invalid_record(line))?;
+        let cap = RE.captures(line).ok_or_else(|| diagnose(line, FIELDS))?;
 
         let source = unsafe { LRecordSource::from_bytes_unchecked(&cap[1]) };
-        let text = as_text(&cap[2]).ok_or_else(|| Error::invalid_record(line))?;
+        let text = text_field(line, 4, line.len(), "text", None)?;
 
         Ok(LRecord { source, text })
     }
-}
-
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
     #[test]
     fn test_parse() {
@@ -66,14 +115,31 @@ mod tests {
         assert_eq!(record.source, LRecordSource::OOI);
         assert_eq!(record.text, "from Eagle Field");
 
+        let record = LRecord::parse(b"LCU::HPPLTPILOT:Kevin.Houlihan").unwrap();
+        assert_eq!(record.source, LRecordSource::Other("CU:".into()));
+        assert_eq!(record.text, ":HPPLTPILOT:Kevin.Houlihan");
+
         assert!(LRecord::parse(b"LOOI").is_err());
     }
 
+    #[test]
+    fn test_to_bytes() {
+        for line in &[&b"LPLTThis flight was my second 1000km attempt"[..], b"LCU::HPPLTPILOT:Kevin.Houlihan"] {
+            assert_eq!(LRecord::parse(line).unwrap().to_bytes(), line.to_vec());
+        }
+    }
+
     proptest! {
         #[test]
         #[allow(unused_must_use)]
         fn parse_doesnt_crash(s in r"\PC*") {
             LRecord::parse(s.as_bytes());
         }
+
+        #[test]
+        fn parse_matches_regex(s in r"\PC*|L[A-Z0-9:a]{3}\n?\PC{0,3}(\n\PC{0,3})?") {
+            let result = LRecord::parse(s.as_bytes()).map_err(|error| error.to_string());
+            prop_assert_eq!(result, parse_regex(s.as_bytes()).map_err(|error| error.to_string()));
+        }
     }
 }
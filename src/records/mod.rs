@@ -1,3 +1,4 @@
+mod a_record;
 mod b_record;
 mod c_record;
 mod d_record;
@@ -10,87 +11,135 @@ mod j_record;
 mod k_record;
 mod l_record;
 
-use ::{Error, Result};
-pub use self::b_record::BRecord;
-pub use self::c_record::{CRecordDeclaration, CRecordTurnpoint};
-pub use self::d_record::DRecord;
+use ::{Error, ErrorKind, Result};
+use ::utils::text::Encoding;
+use ::prelude::*;
+pub use self::a_record::ARecord;
+pub use self::b_record::{BRecord, BRecordRef};
+pub use self::c_record::{CRecord, CRecordDeclaration, CRecordTurnpoint};
+pub use self::d_record::{DRecord, GPSQualifier};
 pub use self::e_record::ERecord;
 pub use self::f_record::FRecord;
 pub use self::g_record::GRecord;
 pub use self::h_record::HRecord;
 pub use self::i_record::IRecord;
 pub use self::j_record::JRecord;
-pub use self::k_record::KRecord;
-pub use self::l_record::LRecord;
+pub use self::k_record::{KRecord, KRecordRef};
+pub use self::l_record::{LRecord, LRecordSource};
 
 /// IGC file record type representing a single line
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum Record {
     /// FR manufacturer and FR serial no.
-    A,
+    A(ARecord),
 
     /// Fix
     B(BRecord),
 
     /// Task/declaration
-    C,
+    C(CRecord),
 
     /// Differential GPS
-    D,
+    D(DRecord),
 
     /// Event
-    E,
+    E(ERecord),
 
     /// Satellite constellation
-    F,
+    F(FRecord),
 
     /// Security
-    G,
+    G(GRecord),
 
     /// File header
-    H,
+    H(HRecord),
 
     /// List of additional data included at end of each B-record
     I(IRecord),
 
     /// List of additional data included at end of each K-record
-    J,
+    J(JRecord),
 
     /// Frequent data, additional to the B-record
-    K,
+    K(KRecord),
 
     /// Logbook/comments
-    L,
+    L(LRecord),
 }
 
 impl Record {
+    /// Parses a line, decoding the text of each text field as UTF-8 or, if
+    /// that fails, as Latin-1.
     pub fn parse(bytes: &[u8]) -> Result<Record> {
+        Record::parse_encoded(bytes, None)
+    }
+
+    /// Parses a line, decoding the text of A, C, E, G, H and L records with
+    /// the `encoding` of the file.
+    pub fn parse_with_encoding(bytes: &[u8], encoding: Encoding) -> Result<Record> {
+        Record::parse_encoded(bytes, Some(encoding))
+    }
+
+    pub(crate) fn parse_encoded(bytes: &[u8], encoding: Option<Encoding>) -> Result<Record> {
         if bytes.is_empty() {
             return Err(Error::invalid_record(bytes))
         }
 
         match bytes[0] {
-            b'A' => Ok(Record::A),
+            b'A' => ARecord::parse_encoded(bytes, encoding).map(Record::A),
             b'B' => BRecord::parse(bytes).map(Record::B),
-            b'C' => Ok(Record::C),
-            b'D' => Ok(Record::D),
-            b'E' => Ok(Record::E),
-            b'F' => Ok(Record::F),
-            b'G' => Ok(Record::G),
-            b'H' => Ok(Record::H),
+            b'C' => CRecord::parse_encoded(bytes, encoding).map(Record::C),
+            b'D' => DRecord::parse(bytes).map(Record::D),
+            b'E' => ERecord::parse_encoded(bytes, encoding).map(Record::E),
+            b'F' => FRecord::parse(bytes).map(Record::F),
+            b'G' => GRecord::parse_encoded(bytes, encoding).map(Record::G),
+            b'H' => HRecord::parse_encoded(bytes, encoding).map(Record::H),
             b'I' => IRecord::parse(bytes).map(Record::I),
-            b'J' => Ok(Record::J),
-            b'K' => Ok(Record::K),
-            b'L' => Ok(Record::L),
-            _ => Err(Error::invalid_record(bytes)),
+            b'J' => JRecord::parse(bytes).map(Record::J),
+            b'K' => KRecord::parse(bytes).map(Record::K),
+            b'L' => LRecord::parse_encoded(bytes, encoding).map(Record::L),
+            _ => Err(Error::parse(ErrorKind::UnknownRecordType, bytes).in_field(1, "record type")),
         }
     }
+
+    /// Formats the record as a single line without line ending. B and K
+    /// records are written without their additions, see `Writer` for
+    /// writing them with the I and J record definitions.
+    ///
+    /// Returns an error for B records with altitudes that can not be
+    /// represented, see `BRecord::to_bytes()`.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(match *self {
+            Record::A(ref record) => record.to_bytes(),
+            Record::B(ref record) => record.to_bytes()?,
+            Record::C(ref record) => record.to_bytes(),
+            Record::D(ref record) => record.to_bytes(),
+            Record::E(ref record) => record.to_bytes(),
+            Record::F(ref record) => record.to_bytes(),
+            Record::G(ref record) => record.to_bytes(),
+            Record::H(ref record) => record.to_bytes(),
+            Record::I(ref record) => record.to_bytes(),
+            Record::J(ref record) => record.to_bytes(),
+            Record::K(ref record) => record.to_bytes(),
+            Record::L(ref record) => record.to_bytes(),
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Record::parse(b"").unwrap_err().kind(), Some(ErrorKind::InvalidRecord));
+        assert_eq!(Record::parse(b"X123").unwrap_err().kind(), Some(ErrorKind::UnknownRecordType));
+        assert_eq!(Record::parse(b"E12345PEV").unwrap_err().kind(), Some(ErrorKind::BadTime));
+        assert_eq!(Record::parse(b"C040516072924000000000a02").unwrap_err().kind(), Some(ErrorKind::BadNumber));
+        assert_eq!(Record::parse(b"C5111359N0010189W").unwrap_err().kind(), Some(ErrorKind::BadCoordinate));
+        assert_eq!(Record::parse(b"HFDT").unwrap_err().kind(), Some(ErrorKind::InvalidRecord));
+    }
+
     proptest! {
         #[test]
         #[allow(unused_must_use)]
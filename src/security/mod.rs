@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use ::ARecord;
+use ::utils::lines::SliceLines;
+
+mod md5;
+mod xcsoar;
+
+pub use self::xcsoar::{XCSOAR_MANUFACTURERS, XcsoarSigner, XcsoarValidator};
+
+/// Result of checking the security (G) records of a file
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Verification {
+    /// The G records match the content of the file
+    Valid,
+
+    /// The content of the file was modified after it was signed, or the G
+    /// records are missing or broken
+    Tampered,
+
+    /// No validator is known for the flight recorder that wrote the file
+    Unsupported,
+}
+
+/// Verification of the G records of a single flight recorder manufacturer
+pub trait SecurityValidator {
+    /// Checks the `signature`, i.e. the joined text of all G records, against
+    /// the signed `content` of a file, see `SignedContent`.
+    fn verify(&self, content: &[u8], signature: &[u8]) -> Verification;
+}
+
+/// Creation of the G records for the files that a `Writer` writes
+pub trait Signer {
+    /// Adds the signed part of the next line, without its line ending, see
+    /// `SignedContent` for which lines are signed.
+    fn update(&mut self, content: &[u8]);
+
+    /// Returns the signature, i.e. the joined text of all G records.
+    fn finish(&mut self) -> String;
+
+    /// Number of characters of the signature in each G record
+    fn line_length(&self) -> usize;
+}
+
+/// Parts of an IGC file that a `SecurityValidator` needs
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SignedContent {
+    /// Manufacturer code of the A record
+    pub manufacturer: String,
+
+    /// All lines that are covered by the G records, without line endings and
+    /// without any separator between them:
+    ///
+    /// - empty lines and G records are left out
+    /// - H records of an official observer (`HO`) or the pilot (`HP`) are
+    ///   left out, since they can be added after the flight
+    /// - L records are only included if their source is the manufacturer code
+    /// - all bytes that are not printable ASCII characters are removed, i.e.
+    ///   control characters like `\r` and non-ASCII text, but not spaces
+    pub content: Vec<u8>,
+
+    /// Text of all G records, joined without line endings
+    pub signature: Vec<u8>,
+}
+
+impl SignedContent {
+    /// Splits a complete IGC file into the signed content and the signature.
+    /// Returns `None` if the first line is not a valid A record.
+    pub fn from_bytes(bytes: &[u8]) -> Option<SignedContent> {
+        let mut lines = SliceLines::new(bytes).map(|(line, _)| line);
+        let manufacturer = lines.next().and_then(|line| ARecord::parse(line).ok())?.manufacturer;
+
+        let mut filter = ContentFilter::default();
+        let mut content = Vec::with_capacity(bytes.len());
+        let mut signature = Vec::new();
+        for (line, _) in SliceLines::new(bytes) {
+            if line.first() == Some(&b'G') {
+                signature.extend(printable(&line[1..]));
+            } else if let Some(signed) = filter.signed_bytes(line) {
+                content.extend(signed);
+            }
+        }
+
+        Some(SignedContent { manufacturer, content, signature })
+    }
+}
+
+/// Selects the signed content of a file line by line, see `SignedContent`
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ContentFilter {
+    manufacturer: Option<String>,
+}
+
+impl ContentFilter {
+    /// Returns the signed part of a line without line ending, or `None` if
+    /// the line is not signed. The manufacturer code for the L records is
+    /// taken from the A record.
+    pub fn signed_bytes(&mut self, line: &[u8]) -> Option<Vec<u8>> {
+        match line.first() {
+            None | Some(b'G') => return None,
+            Some(b'A') => self.manufacturer = ARecord::parse(line).ok().map(|record| record.manufacturer),
+            Some(b'H') if line.get(1) == Some(&b'O') || line.get(1) == Some(&b'P') => return None,
+            Some(b'L') if self.manufacturer.as_ref().map(String::as_bytes) != line.get(1..4) => return None,
+            Some(_) => {},
+        }
+
+        Some(printable(line).collect())
+    }
+}
+
+fn printable(bytes: &[u8]) -> impl Iterator<Item = u8> + '_ {
+    bytes.iter().cloned().filter(|b| (b' '..=b'~').contains(b))
+}
+
+/// Collection of `SecurityValidator`s, keyed by the manufacturer code of the
+/// A record
+#[derive(Default)]
+pub struct SecurityRegistry {
+    validators: HashMap<String, Box<dyn SecurityValidator + Send + Sync>>,
+}
+
+impl SecurityRegistry {
+    pub fn new() -> SecurityRegistry {
+        SecurityRegistry::default()
+    }
+
+    /// Registry with the validators that are part of this crate, i.e. the
+    /// `XcsoarValidator` for XCSoar and LK8000
+    pub fn with_builtin_validators() -> SecurityRegistry {
+        let mut registry = SecurityRegistry::new();
+        for manufacturer in XCSOAR_MANUFACTURERS {
+            registry.register(manufacturer, XcsoarValidator);
+        }
+        registry
+    }
+
+    /// Uses the `validator` for all files with the given three-letter
+    /// `manufacturer` code, replacing any validator that was registered for
+    /// it before.
+    pub fn register<V>(&mut self, manufacturer: &str, validator: V)
+        where V: SecurityValidator + Send + Sync + 'static
+    {
+        self.validators.insert(manufacturer.to_owned(), Box::new(validator));
+    }
+
+    pub fn validator(&self, manufacturer: &str) -> Option<&(dyn SecurityValidator + Send + Sync)> {
+        self.validators.get(manufacturer).map(|validator| &**validator)
+    }
+
+    /// Checks the G records of a complete IGC file with the validator of its
+    /// manufacturer.
+    ///
+    /// Files without a valid A record are reported as `Unsupported`, since
+    /// their manufacturer is not known.
+    pub fn verify(&self, bytes: &[u8]) -> Verification {
+        match SignedContent::from_bytes(bytes) {
+            Some(signed) => self.verify_content(&signed),
+            None => Verification::Unsupported,
+        }
+    }
+
+    pub fn verify_content(&self, signed: &SignedContent) -> Verification {
+        match self.validator(&signed.manufacturer) {
+            Some(validator) => validator.verify(&signed.content, &signed.signature),
+            None => Verification::Unsupported,
+        }
+    }
+}
+
+impl fmt::Debug for SecurityRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut manufacturers: Vec<_> = self.validators.keys().collect();
+        manufacturers.sort();
+        f.debug_struct("SecurityRegistry").field("manufacturers", &manufacturers).finish()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &[u8] = b"AXXXABC\r
+HFDTE040516\r
+HOCIDCOMPETITIONID:W\r
+HPPLTPILOT:Hans\r
+B0810425049315N00610899EA000480023000208\r
+\r
+LXXXrecorder comment\r
+LPLTpilot comment\r
+E104533PEV\xf6\r
+G0123\r
+G4567\r
+";
+
+    /// Signature that is just the sum of all content bytes, in hex
+    struct Checksum;
+
+    impl SecurityValidator for Checksum {
+        fn verify(&self, content: &[u8], signature: &[u8]) -> Verification {
+            let sum = content.iter().fold(0u32, |sum, b| sum.wrapping_add(*b as u32));
+            if format!("{:08X}", sum).as_bytes() == signature {
+                Verification::Valid
+            } else {
+                Verification::Tampered
+            }
+        }
+    }
+
+    fn sign(bytes: &[u8]) -> Vec<u8> {
+        let content = SignedContent::from_bytes(bytes).unwrap().content;
+        let sum = content.iter().fold(0u32, |sum, b| sum.wrapping_add(*b as u32));
+        let mut signed = bytes.to_vec();
+        signed.extend(format!("G{:04X}\r\nG{:04X}\r\n", sum >> 16, sum & 0xffff).bytes());
+        signed
+    }
+
+    #[test]
+    fn test_signed_content() {
+        let signed = SignedContent::from_bytes(EXAMPLE).unwrap();
+        assert_eq!(signed.manufacturer, "XXX");
+        assert_eq!(
+            ::std::str::from_utf8(&signed.content).unwrap(),
+            "AXXXABCHFDTE040516B0810425049315N00610899EA000480023000208LXXXrecorder commentE104533PEV",
+        );
+        assert_eq!(signed.signature, b"01234567");
+
+        assert!(SignedContent::from_bytes(b"").is_none());
+        assert!(SignedContent::from_bytes(b"HFDTE040516\r\nAXXXABC\r\n").is_none());
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry = SecurityRegistry::new();
+        registry.register("XXX", Checksum);
+        assert!(registry.validator("XXX").is_some());
+        assert!(registry.validator("LXN").is_none());
+
+        let bytes = sign(&EXAMPLE[..EXAMPLE.len() - 14]);
+        assert_eq!(registry.verify(&bytes), Verification::Valid);
+
+        // line endings and the records that are added after the flight are not signed
+        let mut changed = String::from_utf8_lossy(&bytes).replace("\r\n", "\n").into_bytes();
+        changed.splice(8..8, b"LPLTanother comment\n".iter().cloned());
+        assert_eq!(registry.verify(&changed), Verification::Valid);
+
+        let tampered = String::from_utf8_lossy(&bytes).replace("PEV", "PEW").into_bytes();
+        assert_eq!(registry.verify(&tampered), Verification::Tampered);
+
+        assert_eq!(registry.verify(b"HFDTE040516\r\n"), Verification::Unsupported);
+        assert_eq!(SecurityRegistry::new().verify(&bytes), Verification::Unsupported);
+        assert_eq!(format!("{:?}", registry), r#"SecurityRegistry { manufacturers: ["XXX"] }"#);
+    }
+}
@@ -0,0 +1,174 @@
+use ::GRecord;
+use super::{SecurityValidator, Signer, SignedContent, Verification};
+use super::md5::Md5;
+
+/// Manufacturer codes of the flight recorders that use the XCSoar G record
+pub const XCSOAR_MANUFACTURERS: &[&str] = &["XCS", "XLK"];
+
+/// Initial states of the four MD5 hashes, as in `GRecord.cpp` of XCSoar
+const KEYS: [[u32; 4]; 4] = [
+    [0x63e54c01, 0x25adab89, 0x44baecfe, 0x60f25476],
+    [0x41e24d03, 0x23b8ebea, 0x4a4bfc9e, 0x640ed89a],
+    [0x61e54e01, 0x22cdab89, 0x48b20cfe, 0x62125476],
+    [0xc1e84fe8, 0x21d1c28a, 0x438e1a12, 0x6c250aee],
+];
+
+/// Number of hex digits in each G record
+const RECORD_LENGTH: usize = 16;
+
+/// Open G record scheme of XCSoar and LK8000
+///
+/// The signature consists of four MD5 hashes of the signed content with
+/// different initial states, written as 128 lower case hex digits in G
+/// records of 16 digits each. The reserved characters of the specification
+/// (`$*,!\^~`) are not part of the hashed content.
+///
+/// Since the keys are public, this detects accidental and naive
+/// modifications, but anyone can create a valid signature.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct XcsoarValidator;
+
+impl XcsoarValidator {
+    /// Signature of the content of a `SignedContent`, i.e. the joined text of
+    /// all G records
+    pub fn signature(&self, content: &[u8]) -> String {
+        let mut signer = XcsoarSigner::new();
+        signer.update(content);
+        signer.finish()
+    }
+
+    /// Creates the G records for a complete IGC file without G records.
+    /// Returns `None` if the first line is not a valid A record.
+    pub fn sign(&self, bytes: &[u8]) -> Option<Vec<GRecord>> {
+        let signed = SignedContent::from_bytes(bytes)?;
+        let signature = self.signature(&signed.content);
+        let records = signature.as_bytes()
+            .chunks(RECORD_LENGTH)
+            .map(|text| GRecord { text: String::from_utf8_lossy(text).into_owned() })
+            .collect();
+        Some(records)
+    }
+}
+
+/// `Signer` for the G records of the `XcsoarValidator`
+#[derive(Debug, Clone)]
+pub struct XcsoarSigner {
+    hashes: [Md5; 4],
+}
+
+impl XcsoarSigner {
+    pub fn new() -> XcsoarSigner {
+        XcsoarSigner { hashes: [Md5::new(KEYS[0]), Md5::new(KEYS[1]), Md5::new(KEYS[2]), Md5::new(KEYS[3])] }
+    }
+}
+
+impl Default for XcsoarSigner {
+    fn default() -> XcsoarSigner {
+        XcsoarSigner::new()
+    }
+}
+
+impl Signer for XcsoarSigner {
+    fn update(&mut self, content: &[u8]) {
+        let content: Vec<u8> = content.iter().cloned().filter(|b| !b"$*,!\\^~".contains(b)).collect();
+        for md5 in &mut self.hashes {
+            md5.update(&content);
+        }
+    }
+
+    fn finish(&mut self) -> String {
+        let mut signature = String::with_capacity(RECORD_LENGTH * 8);
+        for md5 in &self.hashes {
+            for byte in &md5.clone().finish() {
+                signature.push_str(&format!("{:02x}", byte));
+            }
+        }
+        signature
+    }
+
+    fn line_length(&self) -> usize {
+        RECORD_LENGTH
+    }
+}
+
+impl SecurityValidator for XcsoarValidator {
+    fn verify(&self, content: &[u8], signature: &[u8]) -> Verification {
+        if signature.eq_ignore_ascii_case(self.signature(content).as_bytes()) {
+            Verification::Valid
+        } else {
+            Verification::Tampered
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use super::*;
+    use ::SecurityRegistry;
+
+    fn fixture() -> Vec<u8> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/2015-05-14-xlk-sem-01.igc");
+        fs::read(path).unwrap()
+    }
+
+    fn without_g_records(bytes: &[u8]) -> Vec<u8> {
+        bytes.split(|b| *b == b'\n')
+            .filter(|line| !line.is_empty() && line[0] != b'G')
+            .flat_map(|line| line.iter().chain(b"\n"))
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn test_signature() {
+        let signature = XcsoarValidator.signature(b"AXCSABC");
+        assert_eq!(signature.len(), 128);
+        assert!(signature.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)));
+        assert_eq!(&signature[..32], "7787bc44bb760a79a1b02dea6d831937");
+
+        // reserved characters are ignored
+        assert_eq!(XcsoarValidator.signature(b"LXCSa,b"), XcsoarValidator.signature(b"LXCSab"));
+        assert_ne!(XcsoarValidator.signature(b"LXCSa b"), XcsoarValidator.signature(b"LXCSab"));
+    }
+
+    #[test]
+    fn test_sign() {
+        let records = XcsoarValidator.sign(b"AXCSABC\r\nHFDTE040516\r\n").unwrap();
+        assert_eq!(records.len(), 8);
+        assert!(records.iter().all(|record| record.text.len() == 16));
+        assert!(XcsoarValidator.sign(b"HFDTE040516\r\n").is_none());
+
+        let bytes = fixture();
+        let expected: Vec<&[u8]> = bytes.split(|b| *b == b'\n').filter(|line| line.first() == Some(&b'G')).collect();
+        let records = XcsoarValidator.sign(&without_g_records(&bytes)).unwrap();
+        let records: Vec<Vec<u8>> = records.iter().map(GRecord::to_bytes).collect();
+        assert_eq!(records, expected);
+    }
+
+    #[test]
+    fn test_verify() {
+        let registry = SecurityRegistry::with_builtin_validators();
+        let bytes = fixture();
+        assert_eq!(registry.verify(&bytes), Verification::Valid);
+
+        let crlf = String::from_utf8(bytes.clone()).unwrap().replace('\n', "\r\n");
+        assert_eq!(registry.verify(crlf.as_bytes()), Verification::Valid);
+
+        // headers of the official observer and the pilot are not signed
+        let text = String::from_utf8(bytes.clone()).unwrap();
+        let observer = text.replacen("HFDTE", "HOCCLCOMPETITIONCLASS:Club\nHFDTE", 1);
+        assert_eq!(registry.verify(observer.as_bytes()), Verification::Valid);
+
+        let tampered = text.replacen("HFGTYGLIDERTYPE:", "HFGTYGLIDERTYPE:ASW 20", 1);
+        assert_eq!(registry.verify(tampered.as_bytes()), Verification::Tampered);
+
+        let tampered = text.replacen("B1145554533460N", "B1145554533461N", 1);
+        assert_eq!(registry.verify(tampered.as_bytes()), Verification::Tampered);
+
+        assert_eq!(registry.verify(&without_g_records(&bytes)), Verification::Tampered);
+    }
+}
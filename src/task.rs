@@ -0,0 +1,171 @@
+use ::{CRecordDeclaration, CRecordTurnpoint};
+use ::prelude::*;
+
+/// Task declaration consisting of the C record declaration line and all
+/// of the turnpoint lines that follow it
+///
+/// The turnpoint lines are expected in the order takeoff, start,
+/// turnpoints, finish and landing, which means that there should be
+/// `num_turnpoints + 4` of them.
+#[derive(Debug)]
+pub struct Task {
+    pub declaration: CRecordDeclaration,
+
+    /// All turnpoint lines following the declaration, including takeoff,
+    /// start, finish and landing
+    pub points: Vec<CRecordTurnpoint>,
+}
+
+impl Task {
+    pub fn new(declaration: CRecordDeclaration) -> Task {
+        Task { declaration, points: Vec::new() }
+    }
+
+    pub fn with_points(declaration: CRecordDeclaration, points: Vec<CRecordTurnpoint>) -> Task {
+        Task { declaration, points }
+    }
+
+    /// Number of turnpoint lines that the declaration announced
+    pub fn expected_points(&self) -> usize {
+        self.declaration.num_turnpoints as usize + 4
+    }
+
+    /// Checks if the number of turnpoint lines matches the number of
+    /// turnpoints that the declaration announced.
+    pub fn is_consistent(&self) -> bool {
+        self.points.len() == self.expected_points()
+    }
+
+    /// Takeoff point, or `None` if it was not declared
+    pub fn takeoff(&self) -> Option<&CRecordTurnpoint> {
+        declared(self.points.first())
+    }
+
+    pub fn start(&self) -> Option<&CRecordTurnpoint> {
+        declared(self.points.get(1))
+    }
+
+    /// Turnpoints between start and finish
+    pub fn turnpoints(&self) -> &[CRecordTurnpoint] {
+        if self.points.len() < 4 {
+            &[]
+        } else {
+            &self.points[2..self.points.len() - 2]
+        }
+    }
+
+    pub fn finish(&self) -> Option<&CRecordTurnpoint> {
+        match self.points.len() {
+            0..=2 => None,
+            3 => declared(self.points.get(2)),
+            n => declared(self.points.get(n - 2)),
+        }
+    }
+
+    /// Landing point, or `None` if it was not declared
+    pub fn landing(&self) -> Option<&CRecordTurnpoint> {
+        match self.points.len() {
+            0..=3 => None,
+            n => declared(self.points.get(n - 1)),
+        }
+    }
+}
+
+fn declared(point: Option<&CRecordTurnpoint>) -> Option<&CRecordTurnpoint> {
+    point.filter(|point| !point.is_placeholder())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_task(declaration: &[u8], points: &[&[u8]]) -> Task {
+        let declaration = CRecordDeclaration::parse(declaration).unwrap();
+        let points = points.iter().map(|line| CRecordTurnpoint::parse(line).unwrap()).collect();
+        Task::with_points(declaration, points)
+    }
+
+    fn name(point: Option<&CRecordTurnpoint>) -> Option<&str> {
+        point.and_then(|point| point.text.as_deref())
+    }
+
+    #[test]
+    fn test_task() {
+        let task = parse_task(b"C210815093841000000000002500K Triangle", &[
+            b"C5111359N00101899WTAKEOFF Lasham Clubhouse",
+            b"C5110179N00102644WSTART Lasham Start S",
+            b"C5209092N00255227WTURN Sarnesfield",
+            b"C5230147N00017612WTURN Norman Cross",
+            b"C5110179N00102644WFINISH Lasham Start S",
+            b"C5111359N00101899WLANDING Lasham Clubhouse",
+        ]);
+
+        assert!(task.is_consistent());
+        assert_eq!(name(task.takeoff()), Some("TAKEOFF Lasham Clubhouse"));
+        assert_eq!(name(task.start()), Some("START Lasham Start S"));
+        assert_eq!(task.turnpoints().len(), 2);
+        assert_eq!(task.turnpoints()[0].text, Some("TURN Sarnesfield".into()));
+        assert_relative_eq!(task.turnpoints()[0].latitude, 52.15153333333333);
+        assert_relative_eq!(task.turnpoints()[0].longitude, -2.9204499999999998);
+        assert_eq!(task.turnpoints()[1].text, Some("TURN Norman Cross".into()));
+        assert_eq!(name(task.finish()), Some("FINISH Lasham Start S"));
+        assert_eq!(name(task.landing()), Some("LANDING Lasham Clubhouse"));
+    }
+
+    #[test]
+    fn test_placeholders() {
+        let task = parse_task(b"C040516072924000000000002Task", &[
+            b"C0000000N00000000ET",
+            b"C5049383N00611183E1",
+            b"C4903133N00757383E2",
+            b"C5037817N01009517E3",
+            b"C5049383N00611183E4",
+            b"C0000000N00000000EL",
+        ]);
+
+        assert!(task.is_consistent());
+        assert!(task.takeoff().is_none());
+        assert_eq!(name(task.start()), Some("1"));
+        assert_eq!(task.turnpoints().len(), 2);
+        assert_eq!(name(task.finish()), Some("4"));
+        assert!(task.landing().is_none());
+    }
+
+    #[test]
+    fn test_inconsistent() {
+        let task = parse_task(b"C040516072924000000000003Task", &[
+            b"C0000000N00000000ET",
+            b"C5049383N00611183E1",
+            b"C4903133N00757383E2",
+            b"C5049383N00611183E4",
+            b"C0000000N00000000EL",
+        ]);
+
+        assert_eq!(task.expected_points(), 7);
+        assert!(!task.is_consistent());
+        assert_eq!(name(task.start()), Some("1"));
+        assert_eq!(task.turnpoints().len(), 1);
+        assert_eq!(name(task.finish()), Some("4"));
+    }
+
+    #[test]
+    fn test_incomplete() {
+        let task = parse_task(b"C040516072924000000000001Task", &[
+            b"C0000000N00000000ET",
+            b"C5049383N00611183E1",
+            b"C4903133N00757383E2",
+        ]);
+
+        assert!(!task.is_consistent());
+        assert_eq!(name(task.start()), Some("1"));
+        assert!(task.turnpoints().is_empty());
+        assert_eq!(name(task.finish()), Some("2"));
+        assert!(task.landing().is_none());
+
+        let empty = parse_task(b"C040516072924000000000001Task", &[]);
+        assert!(empty.takeoff().is_none());
+        assert!(empty.start().is_none());
+        assert!(empty.finish().is_none());
+    }
+}
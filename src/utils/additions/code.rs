@@ -1,6 +1,8 @@
 use std::str::{FromStr, from_utf8_unchecked};
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, EnumString)]
+use ::prelude::*;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, EnumString, AsRefStr)]
 pub enum AdditionCode {
     /// Linear accelerations in X axes (longitudinal), for aerobatic aircraft
     /// equipped with appropriate sensors feeding to the recorder and IGC file. (so-called "G")
@@ -118,16 +120,27 @@ pub enum AdditionCode {
     /// Wind speed, three numbers in kilometres per hour
     WSP,
 
-    #[strum(default="true")]
+    #[strum(default)]
     Other(String),
 }
 
 impl AdditionCode {
+    /// # Safety
+    ///
+    /// `bytes` must only contain ASCII characters.
     pub unsafe fn from_bytes_unchecked(bytes: &[u8]) -> AdditionCode {
         debug_assert!(bytes.is_ascii());
         let code = from_utf8_unchecked(bytes);
         AdditionCode::from_str(code).unwrap()
     }
+
+    /// Three-letter code as it appears in the file
+    pub fn as_str(&self) -> &str {
+        match *self {
+            AdditionCode::Other(ref code) => code,
+            ref code => code.as_ref(),
+        }
+    }
 }
 
 #[cfg(test)]
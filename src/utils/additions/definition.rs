@@ -1,7 +1,7 @@
 use ::utils::num::parse_int;
 use super::AdditionCode;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct AdditionDef {
     /// Three-Letter-Code describing the addition type
     pub code: AdditionCode,
@@ -18,6 +18,9 @@ impl AdditionDef {
         AdditionDef { code, start_byte, end_byte }
     }
 
+    /// # Safety
+    ///
+    /// `line` must be exactly seven ASCII bytes, the first four of them digits.
     pub unsafe fn parse_unchecked(line: &[u8]) -> AdditionDef {
         debug_assert_eq!(line.len(), 7);
         debug_assert!(line.is_ascii());
@@ -28,6 +31,12 @@ impl AdditionDef {
 
         AdditionDef::new(code, start_byte, end_byte)
     }
+
+    /// Whether the definition describes a non-empty byte range after the
+    /// record type.
+    pub fn is_valid_range(&self) -> bool {
+        self.start_byte >= 1 && self.start_byte <= self.end_byte
+    }
 }
 
 
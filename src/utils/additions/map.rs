@@ -1,34 +1,117 @@
+#[cfg(feature = "std")]
 use std::collections::HashMap;
+#[cfg(not(feature = "std"))]
+use std::collections::BTreeMap;
 
-use ::{Error, Result};
+use ::{Error, ErrorKind, Result};
 use super::{AdditionCode, AdditionDef};
+use ::prelude::*;
 
+#[cfg(feature = "std")]
 pub type AdditionsMap = HashMap<AdditionCode, Vec<u8>>;
 
+/// `HashMap` is not available without `std`
+#[cfg(not(feature = "std"))]
+pub type AdditionsMap = BTreeMap<AdditionCode, Vec<u8>>;
+
 pub trait ParseAdditions {
     fn parse(&self, bytes: &[u8]) -> Result<AdditionsMap>;
 }
 
+pub trait FormatAdditions {
+    /// Appends the additions to the end of the `line`, at the byte positions
+    /// of the definitions.
+    ///
+    /// The definitions have to describe consecutive byte ranges directly
+    /// following the `line` and every addition value has to fit its range
+    /// exactly, otherwise an error is returned.
+    fn format(&self, line: &mut Vec<u8>, additions: &AdditionsMap) -> Result<()>;
+}
+
 impl ParseAdditions for Vec<AdditionDef> {
     fn parse(&self, bytes: &[u8]) -> Result<AdditionsMap> {
-        let input_length = bytes.len();
+        Ok(AdditionsRef::new(bytes, self)?.to_map())
+    }
+}
 
-        let mut additions = AdditionsMap::with_capacity(self.len());
+/// Additions of a B or K record that are only decoded when they are
+/// accessed, borrowing both the line and the addition definitions
+#[derive(Debug, Clone, Copy)]
+pub struct AdditionsRef<'a> {
+    line: &'a [u8],
+    defs: &'a [AdditionDef],
+}
 
-        for AdditionDef { start_byte, end_byte, code } in self {
-            debug_assert!(*start_byte >= 1);
-            debug_assert!(*start_byte <= *end_byte);
+impl<'a> AdditionsRef<'a> {
+    /// Checks that all definitions have a valid byte range and that all
+    /// additions fit into the `line`.
+    pub fn new(line: &'a [u8], defs: &'a [AdditionDef]) -> Result<AdditionsRef<'a>> {
+        for def in defs {
+            let AdditionDef { start_byte, end_byte, ref code } = *def;
+            if !def.is_valid_range() {
+                return Err(Error::parse(ErrorKind::AdditionOutOfRange, line)
+                    .in_field(start_byte as usize, code.as_str()));
+            }
 
-            if *end_byte as usize > input_length || *start_byte as usize > input_length {
-                return Err(Error::invalid_record(bytes));
+            if end_byte as usize > line.len() {
+                return Err(Error::parse(ErrorKind::MissingAddition, line)
+                    .in_field(start_byte as usize, code.as_str()));
             }
+        }
+
+        Ok(AdditionsRef { line, defs })
+    }
+
+    pub fn len(&self) -> usize {
+        self.defs.len()
+    }
 
-            let value = &bytes[(*start_byte as usize - 1)..(*end_byte as usize)];
+    pub fn is_empty(&self) -> bool {
+        self.defs.is_empty()
+    }
+
+    /// Value of the addition with the given code. If the code is defined
+    /// more than once, the last definition wins, like in an `AdditionsMap`.
+    pub fn get(&self, code: &AdditionCode) -> Option<&'a [u8]> {
+        self.defs.iter().rev().find(|def| def.code == *code).map(|def| self.value(def))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&'a AdditionCode, &'a [u8])> {
+        let additions = *self;
+        self.defs.iter().map(move |def| (&def.code, additions.value(def)))
+    }
+
+    fn value(&self, def: &AdditionDef) -> &'a [u8] {
+        &self.line[(def.start_byte as usize - 1)..(def.end_byte as usize)]
+    }
+
+    pub fn to_map(&self) -> AdditionsMap {
+        self.iter().map(|(code, value)| (code.clone(), value.into())).collect()
+    }
+}
+
+impl FormatAdditions for [AdditionDef] {
+    fn format(&self, line: &mut Vec<u8>, additions: &AdditionsMap) -> Result<()> {
+        let mut defs: Vec<_> = self.iter().collect();
+        defs.sort_by_key(|def| def.start_byte);
+
+        for AdditionDef { start_byte, end_byte, code } in defs {
+            let value = match additions.get(code) {
+                Some(value) => value,
+                None => return Err(Error::parse(ErrorKind::MissingAddition, line)
+                    .in_field(*start_byte as usize, code.as_str())),
+            };
+
+            let length = (*end_byte as usize + 1).saturating_sub(*start_byte as usize);
+            if *start_byte as usize != line.len() + 1 || value.len() != length {
+                return Err(Error::parse(ErrorKind::AdditionOutOfRange, line)
+                    .in_field(*start_byte as usize, code.as_str()));
+            }
 
-            additions.insert(code.clone(), value.into());
+            line.extend_from_slice(value);
         }
 
-        Ok(additions)
+        Ok(())
     }
 }
 
@@ -61,18 +144,73 @@ mod tests {
     fn parse_errors() {
         let additions_defs = vec![AdditionDef::new(AdditionCode::HDT, 8, 12)];
         let result = additions_defs.parse(b"K1234560009");
-        assert_eq!(format!("{}", result.unwrap_err()), "invalid record: K1234560009");
+        assert_eq!(format!("{}", result.unwrap_err()), "missing addition at column 8 (HDT): K1234560009");
+    }
+
+    #[test]
+    fn format_example() {
+        let additions_defs = [
+            AdditionDef::new(AdditionCode::SIU, 39, 40),
+            AdditionDef::new(AdditionCode::FXA, 36, 38),
+        ];
+        let mut additions = AdditionsMap::new();
+        additions.insert(AdditionCode::FXA, b"003".to_vec());
+        additions.insert(AdditionCode::SIU, b"08".to_vec());
+
+        let mut line = b"B0818265049456N00610940EA0117301320".to_vec();
+        additions_defs.format(&mut line, &additions).unwrap();
+        assert_eq!(line, b"B0818265049456N00610940EA011730132000308".to_vec());
+    }
+
+    #[test]
+    fn format_errors() {
+        let additions_defs = [AdditionDef::new(AdditionCode::HDT, 8, 10)];
+        let mut additions = AdditionsMap::new();
+        let error = additions_defs.format(&mut b"K123456".to_vec(), &additions).unwrap_err();
+        assert_eq!(error.kind(), Some(ErrorKind::MissingAddition));
+
+        additions.insert(AdditionCode::HDT, b"0090".to_vec());
+        let error = additions_defs.format(&mut b"K123456".to_vec(), &additions).unwrap_err();
+        assert_eq!(error.kind(), Some(ErrorKind::AdditionOutOfRange));
+
+        additions.insert(AdditionCode::HDT, b"090".to_vec());
+        assert!(additions_defs.format(&mut b"K12345".to_vec(), &additions).is_err());
+        assert!(additions_defs.format(&mut b"K123456".to_vec(), &additions).is_ok());
+    }
+
+    #[test]
+    fn additions_ref() {
+        let additions_defs = vec![
+            AdditionDef::new(AdditionCode::FXA, 36, 38),
+            AdditionDef::new(AdditionCode::SIU, 39, 40),
+        ];
+        let additions = AdditionsRef::new(b"B0818265049456N00610940EA011730132000308", &additions_defs).unwrap();
+        assert_eq!(additions.len(), 2);
+        assert_eq!(additions.get(&AdditionCode::FXA), Some(&b"003"[..]));
+        assert_eq!(additions.get(&AdditionCode::SIU), Some(&b"08"[..]));
+        assert_eq!(additions.get(&AdditionCode::ENL), None);
+        assert_eq!(additions.to_map(), additions_defs.parse(b"B0818265049456N00610940EA011730132000308").unwrap());
+
+        assert!(AdditionsRef::new(b"B0818265049456N00610940EA0117301320003", &additions_defs).is_err());
+    }
+
+    #[test]
+    fn additions_ref_out_of_range() {
+        let line = b"B0818265049456N00610940EA011730132000308";
+        for def in &[AdditionDef::new(AdditionCode::FXA, 0, 3), AdditionDef::new(AdditionCode::FXA, 38, 36)] {
+            let additions_defs = [def.clone()];
+            let error = AdditionsRef::new(line, &additions_defs).unwrap_err();
+            assert_eq!(error.kind(), Some(ErrorKind::AdditionOutOfRange));
+            assert!(additions_defs.to_vec().parse(line).is_err());
+        }
     }
 
     proptest! {
         #[test]
         #[allow(unused_must_use)]
         fn parse_doesnt_crash(s in r"[0-9]{4}[A-Z0-9]{3}", t in r"\PC*") {
-            let addition_def = unsafe { AdditionDef::parse_unchecked(s.as_bytes()) };
-            if addition_def.start_byte >= 1 && addition_def.start_byte <= addition_def.end_byte {
-                let additions_defs = vec![addition_def];
-                additions_defs.parse(t.as_bytes());
-            }
+            let additions_defs = vec![unsafe { AdditionDef::parse_unchecked(s.as_bytes()) }];
+            additions_defs.parse(t.as_bytes());
         }
     }
 
@@ -5,5 +5,5 @@ mod support;
 
 pub use self::code::AdditionCode;
 pub use self::definition::AdditionDef;
-pub use self::map::{AdditionsMap, ParseAdditions};
+pub use self::map::{AdditionsMap, AdditionsRef, ParseAdditions, FormatAdditions};
 pub use self::support::AdditionSupport;
@@ -40,6 +40,11 @@ pub trait AdditionSupport {
         if value < 360 { Some(value) } else { None }
     }
 
+    /// Decimal seconds of the UTC time
+    fn decimal_seconds(&self) -> Option<f64> {
+        self.get_fraction_addition(&AdditionCode::TDS)
+    }
+
     fn additional_latitude_decimals(&self) -> Option<f64> {
         self.get_fraction_addition(&AdditionCode::LAD).map(|v| v / 60_000.)
     }
@@ -1,4 +1,5 @@
-use utils::num::parse_int;
+use utils::num::{parse_int, round};
+use ::prelude::*;
 
 pub fn parse_latitude_unchecked(bytes: &[u8]) -> f64 {
     debug_assert_eq!(bytes.len(), 8);
@@ -22,6 +23,29 @@ pub fn parse_longitude_unchecked(bytes: &[u8]) -> f64 {
     if bytes[8] == b'W' { -abs_value } else { abs_value }
 }
 
+/// Formats a latitude as `DDMMmmmN` or `DDMMmmmS`, rounded to thousandths
+/// of a minute
+pub fn format_latitude(value: f64) -> String {
+    let (deg, min) = degrees_and_milliminutes(value);
+    let sign = if value.is_sign_negative() { 'S' } else { 'N' };
+    format!("{:02}{:05}{}", deg, min, sign)
+}
+
+/// Formats a longitude as `DDDMMmmmE` or `DDDMMmmmW`, rounded to thousandths
+/// of a minute
+pub fn format_longitude(value: f64) -> String {
+    let (deg, min) = degrees_and_milliminutes(value);
+    let sign = if value.is_sign_negative() { 'W' } else { 'E' };
+    format!("{:03}{:05}{}", deg, min, sign)
+}
+
+fn degrees_and_milliminutes(value: f64) -> (u32, u32) {
+    // `f64::abs()` is only available without `std` since Rust 1.85
+    let value = if value < 0. { -value } else { value };
+    let milliminutes = round(value * 60000.) as u32;
+    (milliminutes / 60000, milliminutes % 60000)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -32,9 +56,23 @@ mod tests {
         assert_relative_eq!(parse_latitude_unchecked(b"5016925N"), 50.28208333333333);
     }
 
+    #[test]
+    fn test_format_latitude() {
+        assert_eq!(format_latitude(50.28208333333333), "5016925N");
+        assert_eq!(format_latitude(-0.5), "0030000S");
+        assert_eq!(format_latitude(0.), "0000000N");
+        assert_eq!(format_latitude(49.99999999), "5000000N");
+    }
+
+    #[test]
+    fn test_format_longitude() {
+        assert_eq!(format_longitude(9.8852), "00953112E");
+        assert_eq!(format_longitude(-122.25), "12215000W");
+    }
+
     prop_compose! {
         fn latitude()(value in any::<i32>()) -> f64 {
-            (value as f64 / std::i32::MAX as f64) * 90.
+            (value as f64 / i32::MAX as f64) * 90.
         }
     }
 
@@ -50,6 +88,12 @@ mod tests {
             let result = parse_latitude_unchecked(input.as_bytes());
             assert_abs_diff_eq!(result, value, epsilon = 0.0001);
         }
+
+        #[test]
+        fn test_format_latitude_roundtrip(value in latitude()) {
+            let result = parse_latitude_unchecked(format_latitude(value).as_bytes());
+            assert_abs_diff_eq!(result, value, epsilon = 0.00001);
+        }
     }
 
     #[test]
@@ -59,7 +103,7 @@ mod tests {
 
     prop_compose! {
         fn longitude()(value in any::<i32>()) -> f64 {
-            (value as f64 / std::i32::MAX as f64) * 180.
+            (value as f64 / i32::MAX as f64) * 180.
         }
     }
 
@@ -75,5 +119,11 @@ mod tests {
             let result = parse_longitude_unchecked(input.as_bytes());
             assert_abs_diff_eq!(result, value, epsilon = 0.0001);
         }
+
+        #[test]
+        fn test_format_longitude_roundtrip(value in longitude()) {
+            let result = parse_longitude_unchecked(format_longitude(value).as_bytes());
+            assert_abs_diff_eq!(result, value, epsilon = 0.00001);
+        }
     }
 }
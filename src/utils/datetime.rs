@@ -1,6 +1,7 @@
-use utils::num::parse_int;
+use utils::num::{parse_int, round};
+use ::prelude::*;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
 pub struct Date {
     /// The IGC format only requires the last two digits of the year...
     pub year: u8,
@@ -20,9 +21,41 @@ impl Date {
 
         Date::new(year, month, day)
     }
+
+    /// Formats the date as `DDMMYY`
+    pub fn format(&self) -> String {
+        format!("{:02}{:02}{:02}", self.day, self.month, self.year)
+    }
+
+    /// Checks if month and day exist, assuming that the year is between 2000
+    /// and 2099.
+    pub fn is_valid(&self) -> bool {
+        (1..=12).contains(&self.month) && self.day >= 1 && self.day <= self.days_in_month()
+    }
+
+    /// Number of days in the month of this date, assuming that the year is
+    /// between 2000 and 2099.
+    pub fn days_in_month(&self) -> u8 {
+        match self.month {
+            2 => if self.year % 4 == 0 { 29 } else { 28 },
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+
+    /// The date of the following day
+    pub fn next_day(&self) -> Date {
+        if self.day < self.days_in_month() {
+            Date::new(self.year, self.month, self.day + 1)
+        } else if self.month < 12 {
+            Date::new(self.year, self.month + 1, 1)
+        } else {
+            Date::new((self.year + 1) % 100, 1, 1)
+        }
+    }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
 pub struct Time {
     pub hour: u8,
     pub minute: u8,
@@ -41,6 +74,89 @@ impl Time {
 
         Time::from_hms(hour, minute, second)
     }
+
+    /// Formats the time as `HHMMSS`
+    pub fn format(&self) -> String {
+        format!("{:02}{:02}{:02}", self.hour, self.minute, self.second)
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.hour < 24 && self.minute < 60 && self.second < 60
+    }
+
+    pub fn seconds_since_midnight(&self) -> u32 {
+        self.hour as u32 * 3600 + self.minute as u32 * 60 + self.second as u32
+    }
+}
+
+/// UTC date and time of a record, including the optional decimal seconds
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
+pub struct DateTime {
+    pub date: Date,
+    pub time: Time,
+    pub nanosecond: u32,
+}
+
+impl DateTime {
+    pub fn new(date: Date, time: Time) -> DateTime {
+        DateTime { date, time, nanosecond: 0 }
+    }
+
+    /// Sets the fraction of the second. Values outside of `0..1` are
+    /// clamped, so that the nanoseconds stay below one second.
+    pub fn with_decimal_seconds(self, decimal_seconds: f64) -> DateTime {
+        let nanosecond = if decimal_seconds > 0. {
+            round(decimal_seconds.min(0.999_999_999) * 1_000_000_000.) as u32
+        } else {
+            0
+        };
+        DateTime { nanosecond, ..self }
+    }
+}
+
+/// Combines the date from the `DTE` header with the times of the following
+/// records, moving on to the next day if the time wraps around midnight.
+#[derive(Debug, Default)]
+pub struct DateTracker {
+    date: Option<Date>,
+    last_time: Option<Time>,
+}
+
+impl DateTracker {
+    /// Time difference after which a decreasing time is considered to be
+    /// a midnight rollover instead of a glitch of the recorder
+    const ROLLOVER_THRESHOLD: u32 = 12 * 3600;
+
+    pub fn new() -> DateTracker {
+        DateTracker::default()
+    }
+
+    pub fn date(&self) -> Option<Date> {
+        self.date
+    }
+
+    /// Checks if going from `last_time` to `time` is a midnight rollover.
+    pub(crate) fn is_rollover(last_time: Time, time: Time) -> bool {
+        last_time.seconds_since_midnight() > time.seconds_since_midnight() + Self::ROLLOVER_THRESHOLD
+    }
+
+    pub fn set_date(&mut self, date: Date) {
+        self.date = Some(date);
+        self.last_time = None;
+    }
+
+    /// Returns the full date and time for the given record time, or `None`
+    /// if the date is not known yet.
+    pub fn datetime(&mut self, time: Time) -> Option<DateTime> {
+        if let Some(last_time) = self.last_time {
+            if Self::is_rollover(last_time, time) {
+                self.date = self.date.map(|date| date.next_day());
+            }
+        }
+
+        self.last_time = Some(time);
+        self.date.map(|date| DateTime::new(date, time))
+    }
 }
 
 #[cfg(test)]
@@ -60,11 +176,72 @@ mod tests {
         #[test]
         fn test_time_parse_unchecked_with_random_input(input in r"[0-9]{6}") {
             let result = Time::parse_unchecked(input.as_bytes());
-            let formatted_result = format!("{:02}{:02}{:02}", result.hour, result.minute, result.second);
-            prop_assert_eq!(formatted_result, input);
+            prop_assert_eq!(result.format(), input);
         }
     }
 
+    #[test]
+    fn test_next_day() {
+        assert_eq!(Date::new(16, 5, 4).next_day(), Date::new(16, 5, 5));
+        assert_eq!(Date::new(16, 4, 30).next_day(), Date::new(16, 5, 1));
+        assert_eq!(Date::new(16, 2, 28).next_day(), Date::new(16, 2, 29));
+        assert_eq!(Date::new(16, 2, 29).next_day(), Date::new(16, 3, 1));
+        assert_eq!(Date::new(15, 2, 28).next_day(), Date::new(15, 3, 1));
+        assert_eq!(Date::new(15, 12, 31).next_day(), Date::new(16, 1, 1));
+        assert_eq!(Date::new(99, 12, 31).next_day(), Date::new(0, 1, 1));
+    }
+
+    #[test]
+    fn test_is_valid() {
+        assert!(Date::new(16, 2, 29).is_valid());
+        assert!(!Date::new(15, 2, 29).is_valid());
+        assert!(!Date::new(15, 13, 1).is_valid());
+        assert!(!Date::new(15, 1, 0).is_valid());
+        assert!(Time::from_hms(23, 59, 59).is_valid());
+        assert!(!Time::from_hms(24, 0, 0).is_valid());
+        assert!(!Time::from_hms(99, 99, 99).is_valid());
+    }
+
+    #[test]
+    fn test_date_tracker() {
+        let mut tracker = DateTracker::new();
+        assert_eq!(tracker.datetime(Time::from_hms(23, 59, 58)), None);
+
+        tracker.set_date(Date::new(15, 12, 31));
+        assert_eq!(tracker.datetime(Time::from_hms(23, 59, 58)),
+                   Some(DateTime::new(Date::new(15, 12, 31), Time::from_hms(23, 59, 58))));
+        assert_eq!(tracker.datetime(Time::from_hms(23, 59, 57)),
+                   Some(DateTime::new(Date::new(15, 12, 31), Time::from_hms(23, 59, 57))));
+        assert_eq!(tracker.datetime(Time::from_hms(0, 0, 2)),
+                   Some(DateTime::new(Date::new(16, 1, 1), Time::from_hms(0, 0, 2))));
+        assert_eq!(tracker.datetime(Time::from_hms(0, 0, 6)),
+                   Some(DateTime::new(Date::new(16, 1, 1), Time::from_hms(0, 0, 6))));
+        assert_eq!(tracker.date(), Some(Date::new(16, 1, 1)));
+    }
+
+    #[test]
+    fn test_datetime_ordering() {
+        let date = Date::new(16, 5, 4);
+        let a = DateTime::new(date, Time::from_hms(12, 0, 0));
+        let b = a.with_decimal_seconds(0.5);
+        let c = DateTime::new(date, Time::from_hms(12, 0, 1));
+        let d = DateTime::new(date.next_day(), Time::from_hms(0, 0, 0));
+        assert!(a < b);
+        assert!(b < c);
+        assert!(c < d);
+        assert_eq!(b.nanosecond, 500_000_000);
+    }
+
+    #[test]
+    fn test_decimal_seconds_out_of_range() {
+        let datetime = DateTime::new(Date::new(16, 5, 4), Time::from_hms(12, 0, 0));
+        assert_eq!(datetime.with_decimal_seconds(0.999_999_999_9).nanosecond, 999_999_999);
+        assert_eq!(datetime.with_decimal_seconds(1.).nanosecond, 999_999_999);
+        assert_eq!(datetime.with_decimal_seconds(1e30).nanosecond, 999_999_999);
+        assert_eq!(datetime.with_decimal_seconds(-0.5).nanosecond, 0);
+        assert_eq!(datetime.with_decimal_seconds(f64::NAN).nanosecond, 0);
+    }
+
     #[test]
     fn test_date_parse_unchecked() {
         assert_eq!(Date::parse_unchecked(b"000000"), Date::new(0, 0, 0));
@@ -78,8 +255,7 @@ mod tests {
         #[test]
         fn test_date_parse_unchecked_with_random_input(input in r"[0-9]{6}") {
             let result = Date::parse_unchecked(input.as_bytes());
-            let formatted_result = format!("{:02}{:02}{:02}", result.day, result.month, result.year);
-            prop_assert_eq!(formatted_result, input);
+            prop_assert_eq!(result.format(), input);
         }
     }
 }
@@ -1,6 +1,8 @@
 use std::str::{FromStr, from_utf8_unchecked};
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, EnumString)]
+use ::prelude::*;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, EnumString, AsRefStr)]
 pub enum EventCode {
     /// Altimeter pressure setting in hectoPascals (the same as Millibars) as
     /// a 6 digit number PPPPpp including 2 decimal places. For instance,
@@ -87,16 +89,27 @@ pub enum EventCode {
     /// `UNDUP` or `UNDDN`.
     UND,
 
-    #[strum(default="true")]
+    #[strum(default)]
     Other(String),
 }
 
 impl EventCode {
+    /// # Safety
+    ///
+    /// `bytes` must only contain ASCII characters.
     pub unsafe fn from_bytes_unchecked(bytes: &[u8]) -> EventCode {
         debug_assert!(bytes.is_ascii());
         let code = from_utf8_unchecked(bytes);
         EventCode::from_str(code).unwrap()
     }
+
+    /// Three-letter code as it appears in the file
+    pub fn as_str(&self) -> &str {
+        match *self {
+            EventCode::Other(ref code) => code,
+            ref code => code.as_ref(),
+        }
+    }
 }
 
 #[cfg(test)]
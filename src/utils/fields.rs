@@ -0,0 +1,166 @@
+use ::{Error, ErrorKind, Result};
+
+/// Fixed-width field of a record, used to find out which part of a line is
+/// responsible for a parse error
+pub struct Field {
+    pub name: &'static str,
+    pub length: usize,
+    pub kind: ErrorKind,
+    pub is_valid: fn(&[u8]) -> bool,
+}
+
+impl Field {
+    pub const fn new(name: &'static str, length: usize, kind: ErrorKind, is_valid: fn(&[u8]) -> bool) -> Field {
+        Field { name, length, kind, is_valid }
+    }
+
+    pub const fn time(name: &'static str) -> Field {
+        Field::new(name, 6, ErrorKind::BadTime, is_digits)
+    }
+
+    pub const fn date(name: &'static str) -> Field {
+        Field::new(name, 6, ErrorKind::BadDate, is_digits)
+    }
+
+    pub const fn latitude() -> Field {
+        Field::new("latitude", 8, ErrorKind::BadCoordinate, is_latitude)
+    }
+
+    pub const fn longitude() -> Field {
+        Field::new("longitude", 9, ErrorKind::BadCoordinate, is_longitude)
+    }
+
+    pub const fn altitude(name: &'static str) -> Field {
+        Field::new(name, 5, ErrorKind::BadAltitude, is_altitude)
+    }
+
+    pub const fn number(name: &'static str, length: usize) -> Field {
+        Field::new(name, length, ErrorKind::BadNumber, is_digits)
+    }
+
+    pub const fn code(name: &'static str) -> Field {
+        Field::new(name, 3, ErrorKind::InvalidRecord, is_code)
+    }
+}
+
+/// Checks that the line starts with the `record_type` byte, directly followed
+/// by all of the `fields`, and returns the error of `diagnose()` otherwise.
+///
+/// This is all that is needed to parse records with a fixed layout, since the
+/// values can then be read from their known byte offsets.
+pub fn check(line: &[u8], record_type: u8, fields: &[Field]) -> Result<()> {
+    if line.first() == Some(&record_type) && invalid_field(line, fields).is_none() {
+        Ok(())
+    } else {
+        Err(diagnose(line, fields))
+    }
+}
+
+/// Checks the fields of a line that could not be parsed one after the other,
+/// starting after the record type byte, and returns an error pointing at the
+/// first field that is missing or malformed.
+///
+/// If all fields look fine an error without column is returned, since the
+/// problem is then somewhere after the fixed-width part of the line.
+pub fn diagnose(line: &[u8], fields: &[Field]) -> Error {
+    match invalid_field(line, fields) {
+        Some((start, field)) => Error::parse(field.kind, line).in_field(start + 1, field.name),
+        None => Error::invalid_record(line),
+    }
+}
+
+/// Returns the first field that is missing or malformed, and its offset.
+fn invalid_field<'a>(line: &[u8], fields: &'a [Field]) -> Option<(usize, &'a Field)> {
+    let mut start = 1;
+    for field in fields {
+        let end = start + field.length;
+        if line.len() < end || !(field.is_valid)(&line[start..end]) {
+            return Some((start, field));
+        }
+        start = end;
+    }
+
+    None
+}
+
+/// End of the text that starts at `start`, which is either the end of the
+/// line or the first line feed, if the line contains one.
+pub fn text_end(line: &[u8], start: usize) -> usize {
+    line[start..].iter().position(|b| *b == b'\n').map_or(line.len(), |end| start + end)
+}
+
+pub fn is_digits(bytes: &[u8]) -> bool {
+    bytes.iter().all(u8::is_ascii_digit)
+}
+
+pub fn is_code(bytes: &[u8]) -> bool {
+    bytes.iter().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+}
+
+pub fn is_latitude(bytes: &[u8]) -> bool {
+    is_digits(&bytes[..7]) && (bytes[7] == b'N' || bytes[7] == b'S')
+}
+
+pub fn is_longitude(bytes: &[u8]) -> bool {
+    is_digits(&bytes[..8]) && (bytes[8] == b'E' || bytes[8] == b'W')
+}
+
+pub fn is_altitude(bytes: &[u8]) -> bool {
+    is_digits(bytes) || (bytes[0] == b'-' && is_digits(&bytes[1..]))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIELDS: &[Field] = &[
+        Field::time("time"),
+        Field::latitude(),
+        Field::altitude("altitude"),
+    ];
+
+    fn assert_diagnosis(line: &[u8], kind: ErrorKind, column: Option<usize>) {
+        match diagnose(line, FIELDS) {
+            Error::Parse(error) => {
+                assert_eq!(error.kind, kind);
+                assert_eq!(error.column, column);
+            },
+            #[cfg(feature = "std")]
+            error => panic!("unexpected error: {:?}", error),
+        }
+    }
+
+    #[test]
+    fn test_diagnose() {
+        assert_diagnosis(b"X", ErrorKind::BadTime, Some(2));
+        assert_diagnosis(b"X12a456", ErrorKind::BadTime, Some(2));
+        assert_diagnosis(b"X1234565016925", ErrorKind::BadCoordinate, Some(8));
+        assert_diagnosis(b"X1234565016925X", ErrorKind::BadCoordinate, Some(8));
+        assert_diagnosis(b"X1234565016925N-00a1", ErrorKind::BadAltitude, Some(16));
+        assert_diagnosis(b"X1234565016925N-0001", ErrorKind::InvalidRecord, None);
+    }
+
+    #[test]
+    fn test_check() {
+        assert!(check(b"X1234565016925N-0001", b'X', FIELDS).is_ok());
+        assert!(check(b"X1234565016925N-0001 and more", b'X', FIELDS).is_ok());
+        assert!(check(b"Y1234565016925N-0001", b'X', FIELDS).is_err());
+        assert!(check(b"X1234565016925N-001", b'X', FIELDS).is_err());
+    }
+
+    #[test]
+    fn test_text_end() {
+        assert_eq!(text_end(b"E123", 2), 4);
+        assert_eq!(text_end(b"E12\n3", 2), 3);
+        assert_eq!(text_end(b"E12", 3), 3);
+    }
+
+    proptest! {
+        #[test]
+        #[allow(unused_must_use)]
+        fn diagnose_doesnt_crash(s in r"\PC*") {
+            diagnose(s.as_bytes(), FIELDS);
+        }
+    }
+}
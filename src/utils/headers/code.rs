@@ -1,10 +1,16 @@
 use std::str::{FromStr, from_utf8_unchecked};
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, EnumString)]
+use ::prelude::*;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, EnumString, AsRefStr)]
 pub enum HeaderCode {
     /// Date, expressed as DDMMYY
     DTE,
 
+    /// Fix accuracy in metres. Removed from the required headers in
+    /// Amendment 4, but still written by many flight recorders.
+    FXA,
+
     /// Pilot-in-charge (aircraft commander),
     /// family name first then given name(s) as required
     PLT,
@@ -12,6 +18,10 @@ pub enum HeaderCode {
     /// required (same format as PLT for pilot-in-charge). For aircraft with
     /// more than two crew, use CM3 and so forth if required.
     CM2,
+    /// Third Crew Member's Name (same format as PLT for pilot-in-charge)
+    CM3,
+    /// Obsolete code, now use CM2. Was Second Crew Member's Name
+    SCM,
 
     /// Obsolete code, now use DB1. Was Date of Birth of the pilot in the
     /// previous line of the H record (DDMMYY)
@@ -22,6 +32,8 @@ pub enum HeaderCode {
     /// Date of Birth of second crew member in format DDMMYY. For aircraft
     /// with more than two crew, use DB3, DB4 etc.
     DB2,
+    /// Date of Birth of third crew member in format DDMMYY
+    DB3,
 
     /// Glider type, manufacturer, model
     GTY,
@@ -31,6 +43,13 @@ pub enum HeaderCode {
     CID,
     /// Competition class
     CCL,
+    /// Club or organisation, and country, from which flown or operated
+    /// (eg Elmira US, Lasham UK)
+    CLB,
+    /// Site, Name, region, nation etc.
+    SIT,
+    /// OO ID - OO equipment observation
+    OOI,
 
     /// Geodetic Datum in use for lat/long records (for IGC purposes this must
     /// be set to WGS84)
@@ -45,28 +64,96 @@ pub enum HeaderCode {
     /// GPS (US GNS System), followed by receiver maker, type & version
     /// letter/number.
     GPS,
+    /// GLONASS (Russian GNS System), followed by receiver maker, type &
+    /// version letter/number.
+    GLO,
+    /// Galileo (European GNS System), followed by receiver maker, type &
+    /// version letter/number.
+    GAL,
+    /// BeiDou 2 GNS System from the People's Republic of China
+    BEI,
     /// Pressure Altitude Sensor, manufacturer, model, etc (in the H record
     /// line this is followed by the maximum altitude processed by the FR)
     PRS,
     /// Flight Recorder Security. To be used where a security fault has been
     /// detected such as the recorder internal security system (microswitch)
     /// having operated.
+    ///
+    /// The line is usually written as `HFFRSSECSUSPECTUSEVALIPROG:TEXT`,
+    /// but shorter long names like `HFFRSSECURITY:` exist too.
     FRS,
 
+    /// Time Zone Offset, hours from UTC to local time.
+    TZN,
+
+    /// Altimeter pressure setting in hectoPascals as a 6 digit number
+    /// PPPPpp including 2 decimal places
+    ATS,
+
+    /// GNSS altitude reference (non-IGC FRs): `ELL` for the WGS84
+    /// ellipsoid, `GEO` for the WGS84 geoid, `NKN` if not known and `NIL`
+    /// if GNSS altitude is not recorded
+    ALG,
+    /// Pressure altitude reference (non-IGC FRs): `ISA` for ICAO ISA, `MSL`
+    /// for above mean sea level, `NKN` if not known and `NIL` if pressure
+    /// altitude is not recorded
+    ALP,
+
+    /// Units of measure used in the file
+    UNT,
+
+    /// Download software external to the recorder (non-IGC FRs): program
+    /// name, program version and date/time of the download (DDMMYYHHMM)
+    SOF,
+    /// Variant on the IGC file specification (non-IGC FRs)
+    FSP,
+
     /// Means of Propulsion.
     /// (Maker, ON/OFF, Acoustic / ECurrent / Other, Sensor Model)
     MOP,
 
-    #[strum(default="true")]
+    #[strum(default)]
     Other(String),
 }
 
 impl HeaderCode {
+    /// # Safety
+    ///
+    /// `bytes` must only contain ASCII characters.
     pub unsafe fn from_bytes_unchecked(bytes: &[u8]) -> HeaderCode {
         debug_assert!(bytes.is_ascii());
         let code = from_utf8_unchecked(bytes);
         HeaderCode::from_str(code).unwrap()
     }
+
+    /// Three-letter code as it appears in the file
+    pub fn as_str(&self) -> &str {
+        match *self {
+            HeaderCode::Other(ref code) => code,
+            ref code => code.as_ref(),
+        }
+    }
+
+    /// Long name that is written between the code and the colon separator
+    /// for the required headers of the specification (see A3.2.4)
+    pub fn long_name(&self) -> Option<&'static str> {
+        match *self {
+            HeaderCode::DTE => Some("DATE"),
+            HeaderCode::PLT => Some("PILOTINCHARGE"),
+            HeaderCode::CM2 => Some("CREW2"),
+            HeaderCode::GTY => Some("GLIDERTYPE"),
+            HeaderCode::GID => Some("GLIDERID"),
+            HeaderCode::DTM => Some("GPSDATUM"),
+            HeaderCode::RFW => Some("FIRMWAREVERSION"),
+            HeaderCode::RHW => Some("HARDWAREVERSION"),
+            HeaderCode::FTY => Some("FRTYPE"),
+            HeaderCode::GPS => Some("RECEIVER"),
+            HeaderCode::PRS => Some("PRESSALTSENSOR"),
+            HeaderCode::CID => Some("COMPETITIONID"),
+            HeaderCode::CCL => Some("COMPETITIONCLASS"),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -78,6 +165,9 @@ mod tests {
     fn test_from_str() {
         assert_eq!(HeaderCode::from_str("PLT").unwrap(), HeaderCode::PLT);
         assert_eq!(HeaderCode::from_str("CM2").unwrap(), HeaderCode::CM2);
+        assert_eq!(HeaderCode::from_str("TZN").unwrap(), HeaderCode::TZN);
+        assert_eq!(HeaderCode::from_str("ALG").unwrap(), HeaderCode::ALG);
+        assert_eq!(HeaderCode::from_str("ALP").unwrap(), HeaderCode::ALP);
         assert_eq!(HeaderCode::from_str("cm2").unwrap(), HeaderCode::Other("cm2".into()));
         assert_eq!(HeaderCode::from_str("???").unwrap(), HeaderCode::Other("???".into()));
         assert_eq!(HeaderCode::from_str("").unwrap(), HeaderCode::Other("".into()));
@@ -94,6 +184,13 @@ mod tests {
         assert_eq!(unsafe { HeaderCode::from_bytes_unchecked(b"foobar") }, HeaderCode::Other("foobar".into()));
     }
 
+    #[test]
+    fn test_as_str() {
+        assert_eq!(HeaderCode::PLT.as_str(), "PLT");
+        assert_eq!(HeaderCode::CM2.as_str(), "CM2");
+        assert_eq!(HeaderCode::Other("XYZ".into()).as_str(), "XYZ");
+    }
+
     proptest! {
         #[test]
         #[allow(unused_must_use)]
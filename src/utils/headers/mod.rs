@@ -1,5 +1,7 @@
 mod code;
 mod source;
+mod value;
 
 pub use self::code::HeaderCode;
 pub use self::source::HeaderSource;
+pub use self::value::{HeaderValue, Datum, Sensor, Unit, UtcOffset, GnssAltitudeReference, PressureAltitudeReference};
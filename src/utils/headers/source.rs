@@ -14,6 +14,14 @@ impl HeaderSource {
             _ => HeaderSource::Other(byte as char),
         }
     }
+
+    pub fn to_char(&self) -> char {
+        match *self {
+            HeaderSource::FlightRecorder => 'F',
+            HeaderSource::Observer => 'O',
+            HeaderSource::Other(source) => source,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -28,6 +36,13 @@ mod tests {
         assert_eq!(HeaderSource::from_byte_unchecked(b'?'), HeaderSource::Other('?'));
     }
 
+    #[test]
+    fn test_to_char() {
+        assert_eq!(HeaderSource::FlightRecorder.to_char(), 'F');
+        assert_eq!(HeaderSource::Observer.to_char(), 'O');
+        assert_eq!(HeaderSource::Other('P').to_char(), 'P');
+    }
+
     proptest! {
         #[test]
         #[allow(unused_must_use)]
@@ -0,0 +1,664 @@
+use ::{Date, DateTime, Time};
+use ::utils::headers::HeaderCode;
+use ::utils::num::{parse_int, round};
+use ::prelude::*;
+
+/// Typed value of a header (H record) line
+#[derive(Debug, PartialEq)]
+pub enum HeaderValue {
+    /// `DTE`: UTC date of the flight and the optional flight number on that day
+    Date { date: Date, flight_number: Option<u8> },
+
+    /// `FXA`: Fix accuracy in metres
+    FixAccuracy(u16),
+
+    /// `DTM`: Geodetic datum
+    Datum(Datum),
+
+    /// `GPS`, `GLO`, `GAL`, `BEI` and `PRS`: GNSS receiver or pressure
+    /// altitude sensor
+    Sensor(Sensor),
+
+    /// `TZN`: Offset from UTC to local time
+    TimeZone(UtcOffset),
+
+    /// `FTY`: FR manufacturer and model
+    FrType { manufacturer: String, model: Option<String> },
+
+    /// `DB1`, `DB2`, `DB3` and `DOB`: Date of birth of a crew member
+    DateOfBirth(Date),
+
+    /// `ATS`: Altimeter pressure setting in hectoPascals
+    AltimeterSetting(f64),
+
+    /// `ALG`: Reference of the GNSS altitude
+    GnssAltitude(GnssAltitudeReference),
+
+    /// `ALP`: Reference of the pressure altitude
+    PressureAltitude(PressureAltitudeReference),
+
+    /// `SOF`: Download software and the UTC date and time of the download
+    Software { name: String, version: Option<String>, downloaded: Option<DateTime> },
+
+    /// `FRS`: Security of the recorder is suspect, with the optional
+    /// description of the likely fault
+    SecuritySuspect(Option<String>),
+
+    /// `UNT`: Units of measure used in the file
+    Units(Vec<Unit>),
+
+    /// Raw text of unknown or malformed values
+    Text(String),
+}
+
+impl HeaderValue {
+    /// Decodes the text of a header line depending on its code. Falls back to
+    /// `HeaderValue::Text` for codes without typed representation and for
+    /// values that can not be decoded.
+    pub fn parse(code: &HeaderCode, text: &str) -> HeaderValue {
+        let value = match *code {
+            HeaderCode::DTE => parse_date(text),
+            HeaderCode::FXA => parse_int(text.trim().as_bytes()).map(HeaderValue::FixAccuracy),
+            HeaderCode::DTM => Datum::parse(text).map(HeaderValue::Datum),
+            HeaderCode::GPS |
+            HeaderCode::GLO |
+            HeaderCode::GAL |
+            HeaderCode::BEI => Sensor::parse(text, true).map(HeaderValue::Sensor),
+            HeaderCode::PRS => Sensor::parse(text, false).map(HeaderValue::Sensor),
+            HeaderCode::TZN => UtcOffset::parse(text).map(HeaderValue::TimeZone),
+            HeaderCode::FTY => parse_fr_type(text),
+            HeaderCode::DOB |
+            HeaderCode::DB1 |
+            HeaderCode::DB2 |
+            HeaderCode::DB3 => parse_ddmmyy(text.trim().as_bytes()).map(HeaderValue::DateOfBirth),
+            HeaderCode::ATS => parse_altimeter_setting(text),
+            HeaderCode::ALG => GnssAltitudeReference::parse(text).map(HeaderValue::GnssAltitude),
+            HeaderCode::ALP => PressureAltitudeReference::parse(text).map(HeaderValue::PressureAltitude),
+            HeaderCode::SOF => parse_software(text),
+            HeaderCode::FRS => Some(parse_security(text)),
+            HeaderCode::UNT => parse_units(text),
+            _ => None,
+        };
+
+        value.unwrap_or_else(|| HeaderValue::Text(text.into()))
+    }
+}
+
+// Examples:
+//
+// 040516
+// 040516,01
+fn parse_date(text: &str) -> Option<HeaderValue> {
+    let bytes = text.trim().as_bytes();
+    if bytes.len() < 6 {
+        return None;
+    }
+
+    let date = parse_ddmmyy(&bytes[..6])?;
+
+    let flight_number = match &bytes[6..] {
+        b"" => None,
+        rest if rest[0] == b',' => Some(parse_int(trim_bytes(&rest[1..]))?),
+        _ => return None,
+    };
+
+    Some(HeaderValue::Date { date, flight_number })
+}
+
+fn parse_ddmmyy(bytes: &[u8]) -> Option<Date> {
+    if bytes.len() != 6 || !bytes.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+
+    let date = Date::parse_unchecked(bytes);
+    if date.day < 1 || date.day > 31 || date.month < 1 || date.month > 12 {
+        return None;
+    }
+
+    Some(date)
+}
+
+// Examples:
+//
+// 101325
+// 098075
+fn parse_altimeter_setting(text: &str) -> Option<HeaderValue> {
+    let bytes = text.trim().as_bytes();
+    if bytes.len() != 6 {
+        return None;
+    }
+
+    let value: u32 = parse_int(bytes)?;
+    Some(HeaderValue::AltimeterSetting(value as f64 / 100.))
+}
+
+// Examples:
+//
+// GpsDump,4.53,1907102039
+fn parse_software(text: &str) -> Option<HeaderValue> {
+    let mut fields = text.split(',').map(str::trim);
+
+    let name = fields.next().filter(|name| !name.is_empty())?;
+    let version = fields.next().filter(|version| !version.is_empty());
+    let downloaded = match fields.next() {
+        None => None,
+        Some(field) => {
+            let bytes = field.as_bytes();
+            if bytes.len() != 10 || !bytes.iter().all(u8::is_ascii_digit) {
+                return None;
+            }
+
+            let date = parse_ddmmyy(&bytes[..6])?;
+            let time = Time::from_hms(parse_int(&bytes[6..8])?, parse_int(&bytes[8..10])?, 0);
+            if !time.is_valid() {
+                return None;
+            }
+
+            Some(DateTime::new(date, time))
+        },
+    };
+
+    Some(HeaderValue::Software { name: name.into(), version: version.map(Into::into), downloaded })
+}
+
+// Examples:
+//
+// Security Micro Operated FR re-set needed
+// SECSUSPECTUSEVALIPROG (long name without colon)
+fn parse_security(text: &str) -> HeaderValue {
+    let text = text.trim();
+    let text = text.trim_start_matches("SECSUSPECTUSEVALIPROG").trim();
+    HeaderValue::SecuritySuspect(if text.is_empty() { None } else { Some(text.into()) })
+}
+
+// Examples:
+//
+// m,km/h,m/s
+// ft kt hPa
+fn parse_units(text: &str) -> Option<HeaderValue> {
+    let units: Vec<_> = text.split(&[',', ';', ' '][..])
+        .filter(|unit| !unit.is_empty())
+        .map(Unit::parse)
+        .collect();
+
+    if units.is_empty() {
+        return None;
+    }
+
+    Some(HeaderValue::Units(units))
+}
+
+// Examples:
+//
+// Flarm-IGC
+// LXNAVIGATION,LX7007F
+// LK8000 PNA GENERIC
+fn parse_fr_type(text: &str) -> Option<HeaderValue> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+
+    let (manufacturer, model) = split_name(text);
+    Some(HeaderValue::FrType { manufacturer: manufacturer.into(), model: model.map(Into::into) })
+}
+
+/// Splits at the first comma, or at the first space if there is no comma.
+fn split_name(text: &str) -> (&str, Option<&str>) {
+    let index = text.find(',').or_else(|| text.find(' '));
+    match index {
+        None => (text, None),
+        Some(index) => {
+            let rest = text[index + 1..].trim();
+            (text[..index].trim(), if rest.is_empty() { None } else { Some(rest) })
+        }
+    }
+}
+
+fn trim_bytes(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|b| *b != b' ').unwrap_or(bytes.len());
+    let end = bytes.iter().rposition(|b| *b != b' ').map_or(start, |i| i + 1);
+    &bytes[start..end]
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Datum {
+    WGS84,
+    Other(String),
+}
+
+impl Datum {
+    // Examples:
+    //
+    // WGS84
+    // WGS-1984
+    // 100 (legacy IGC code for WGS84)
+    pub fn parse(text: &str) -> Option<Datum> {
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+
+        let normalized: String = text.chars()
+            .filter(|c| *c != '-' && *c != ' ' && *c != '_')
+            .flat_map(char::to_uppercase)
+            .collect();
+
+        match normalized.as_str() {
+            "WGS84" | "WGS1984" | "100" => Some(Datum::WGS84),
+            _ => Some(Datum::Other(text.into())),
+        }
+    }
+}
+
+/// Unit of measure of the `UNT` header
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Unit {
+    Metres,
+    Feet,
+    Kilometres,
+    NauticalMiles,
+    StatuteMiles,
+    KilometresPerHour,
+    Knots,
+    MilesPerHour,
+    MetresPerSecond,
+    FeetPerMinute,
+    Hectopascals,
+    InchesOfMercury,
+    Other(String),
+}
+
+impl Unit {
+    pub fn parse(text: &str) -> Unit {
+        match text.trim().to_lowercase().as_str() {
+            "m" | "metres" | "meters" => Unit::Metres,
+            "ft" | "feet" => Unit::Feet,
+            "km" | "kilometres" | "kilometers" => Unit::Kilometres,
+            "nm" | "nmi" => Unit::NauticalMiles,
+            "mi" | "sm" | "miles" => Unit::StatuteMiles,
+            "km/h" | "kmh" | "kph" => Unit::KilometresPerHour,
+            "kt" | "kts" | "knots" => Unit::Knots,
+            "mph" => Unit::MilesPerHour,
+            "m/s" | "mps" => Unit::MetresPerSecond,
+            "ft/min" | "fpm" => Unit::FeetPerMinute,
+            "hpa" | "mb" | "mbar" => Unit::Hectopascals,
+            "inhg" => Unit::InchesOfMercury,
+            _ => Unit::Other(text.trim().into()),
+        }
+    }
+}
+
+/// Reference of the GNSS altitude (`ALG` header)
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GnssAltitudeReference {
+    /// `ELL`: WGS84 ellipsoid
+    Ellipsoid,
+    /// `GEO`: WGS84 geoid (approximately sea level)
+    Geoid,
+    /// `NKN`: Not known
+    NotKnown,
+    /// `NIL`: GNSS altitude is not recorded
+    NotRecorded,
+}
+
+impl GnssAltitudeReference {
+    pub fn parse(text: &str) -> Option<GnssAltitudeReference> {
+        match text.trim() {
+            "ELL" => Some(GnssAltitudeReference::Ellipsoid),
+            "GEO" => Some(GnssAltitudeReference::Geoid),
+            "NKN" => Some(GnssAltitudeReference::NotKnown),
+            "NIL" => Some(GnssAltitudeReference::NotRecorded),
+            _ => None,
+        }
+    }
+}
+
+/// Reference of the pressure altitude (`ALP` header)
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PressureAltitudeReference {
+    /// `ISA`: ICAO standard atmosphere
+    ISA,
+    /// `MSL`: Above mean sea level
+    MSL,
+    /// `NKN`: Not known
+    NotKnown,
+    /// `NIL`: Pressure altitude is not recorded
+    NotRecorded,
+}
+
+impl PressureAltitudeReference {
+    pub fn parse(text: &str) -> Option<PressureAltitudeReference> {
+        match text.trim() {
+            "ISA" => Some(PressureAltitudeReference::ISA),
+            "MSL" => Some(PressureAltitudeReference::MSL),
+            "NKN" => Some(PressureAltitudeReference::NotKnown),
+            "NIL" => Some(PressureAltitudeReference::NotRecorded),
+            _ => None,
+        }
+    }
+}
+
+/// Details of a GNSS receiver (`GPS`, `GLO`, `GAL` or `BEI`) or pressure
+/// altitude sensor (`PRS`)
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Sensor {
+    pub maker: Option<String>,
+    pub model: Option<String>,
+
+    /// Number of receiver channels (only used for GNSS receivers)
+    pub channels: Option<u8>,
+
+    /// Maximum altitude in metres that can be recorded
+    pub max_altitude: Option<u32>,
+}
+
+impl Sensor {
+    // Examples:
+    //
+    // uBLOX,LEA-4P,16,max9000m
+    // LEA-4P,16,8191
+    // INTERSEMA,MS5534A,max8000m
+    // Intersema MS5534B,8191
+    pub fn parse(text: &str, with_channels: bool) -> Option<Sensor> {
+        let mut fields: Vec<_> = text.split(',').map(str::trim).collect();
+        while fields.last() == Some(&"") {
+            fields.pop();
+        }
+
+        if fields.is_empty() {
+            return None;
+        }
+
+        let mut sensor = Sensor::default();
+
+        if let Some(max_altitude) = fields.last().and_then(|field| parse_altitude(field)) {
+            sensor.max_altitude = Some(max_altitude);
+            fields.pop();
+        }
+
+        if with_channels {
+            if let Some(channels) = fields.last().and_then(|field| parse_int(field.as_bytes())) {
+                sensor.channels = Some(channels);
+                fields.pop();
+            }
+        }
+
+        match fields.len() {
+            0 => {},
+            1 => match split_name(fields[0]) {
+                (model, None) => sensor.model = Some(model.into()),
+                (maker, Some(model)) => {
+                    sensor.maker = Some(maker.into());
+                    sensor.model = Some(model.into());
+                },
+            },
+            _ => {
+                sensor.maker = Some(fields[0].into());
+                sensor.model = Some(fields[1..].join(","));
+            },
+        }
+
+        Some(sensor)
+    }
+}
+
+// Examples:
+//
+// 8191
+// max9000m
+// 30000ft
+fn parse_altitude(text: &str) -> Option<u32> {
+    let lowercase = text.to_lowercase();
+    let text = lowercase.trim_start_matches("max").trim();
+    if text.ends_with("ft") {
+        let feet: u32 = parse_int(text.trim_end_matches("ft").trim().as_bytes())?;
+        Some(round(feet as f64 * 0.3048) as u32)
+    } else {
+        parse_int(text.trim_end_matches('m').trim().as_bytes())
+    }
+}
+
+/// Offset from UTC to local time
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct UtcOffset {
+    pub minutes: i16,
+}
+
+impl UtcOffset {
+    pub fn from_minutes(minutes: i16) -> UtcOffset {
+        UtcOffset { minutes }
+    }
+
+    pub fn hours(&self) -> f64 {
+        self.minutes as f64 / 60.
+    }
+
+    // Examples:
+    //
+    // +2
+    // -3.50
+    // +05:30
+    // UTC+10
+    pub fn parse(text: &str) -> Option<UtcOffset> {
+        let text = text.trim();
+        let text = text.trim_start_matches("UTC").trim_start_matches("GMT").trim();
+        if text.is_empty() {
+            return None;
+        }
+
+        let (sign, text) = match text.as_bytes()[0] {
+            b'-' => (-1., &text[1..]),
+            b'+' => (1., &text[1..]),
+            _ => (1., text),
+        };
+
+        if !text.bytes().all(|b| b.is_ascii_digit() || b == b'.' || b == b':') {
+            return None;
+        }
+
+        let hours = match text.find(':') {
+            Some(index) => {
+                let hours: u8 = parse_int(&text.as_bytes()[..index])?;
+                let minutes: u8 = parse_int(&text.as_bytes()[index + 1..])?;
+                if minutes >= 60 { return None }
+                hours as f64 + minutes as f64 / 60.
+            },
+            None => text.parse::<f64>().ok()?,
+        };
+
+        if hours > 14. {
+            return None;
+        }
+
+        Some(UtcOffset::from_minutes(round(sign * hours * 60.) as i16))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::HRecord;
+
+    fn sensor(maker: Option<&str>, model: Option<&str>, channels: Option<u8>, max_altitude: Option<u32>) -> HeaderValue {
+        HeaderValue::Sensor(Sensor {
+            maker: maker.map(Into::into),
+            model: model.map(Into::into),
+            channels,
+            max_altitude,
+        })
+    }
+
+    #[test]
+    fn test_date() {
+        assert_eq!(HeaderValue::parse(&HeaderCode::DTE, "040516"),
+                   HeaderValue::Date { date: Date::new(16, 5, 4), flight_number: None });
+        assert_eq!(HeaderValue::parse(&HeaderCode::DTE, "040516,01"),
+                   HeaderValue::Date { date: Date::new(16, 5, 4), flight_number: Some(1) });
+        assert_eq!(HeaderValue::parse(&HeaderCode::DTE, "040516, 12"),
+                   HeaderValue::Date { date: Date::new(16, 5, 4), flight_number: Some(12) });
+        assert_eq!(HeaderValue::parse(&HeaderCode::DTE, "401316"), HeaderValue::Text("401316".into()));
+        assert_eq!(HeaderValue::parse(&HeaderCode::DTE, "0405"), HeaderValue::Text("0405".into()));
+        assert_eq!(HeaderValue::parse(&HeaderCode::DTE, "040516X"), HeaderValue::Text("040516X".into()));
+    }
+
+    #[test]
+    fn test_fix_accuracy() {
+        assert_eq!(HeaderValue::parse(&HeaderCode::FXA, "500"), HeaderValue::FixAccuracy(500));
+        assert_eq!(HeaderValue::parse(&HeaderCode::FXA, "100"), HeaderValue::FixAccuracy(100));
+        assert_eq!(HeaderValue::parse(&HeaderCode::FXA, "abc"), HeaderValue::Text("abc".into()));
+    }
+
+    #[test]
+    fn test_datum() {
+        assert_eq!(HeaderValue::parse(&HeaderCode::DTM, "WGS84"), HeaderValue::Datum(Datum::WGS84));
+        assert_eq!(HeaderValue::parse(&HeaderCode::DTM, "WGS-1984"), HeaderValue::Datum(Datum::WGS84));
+        assert_eq!(HeaderValue::parse(&HeaderCode::DTM, "WGS-84"), HeaderValue::Datum(Datum::WGS84));
+        assert_eq!(HeaderValue::parse(&HeaderCode::DTM, "100"), HeaderValue::Datum(Datum::WGS84));
+        assert_eq!(HeaderValue::parse(&HeaderCode::DTM, "ETRS89"), HeaderValue::Datum(Datum::Other("ETRS89".into())));
+        assert_eq!(HeaderValue::parse(&HeaderCode::DTM, ""), HeaderValue::Text("".into()));
+    }
+
+    #[test]
+    fn test_sensors() {
+        assert_eq!(HeaderValue::parse(&HeaderCode::GPS, "LEA-4P,16,8191"),
+                   sensor(None, Some("LEA-4P"), Some(16), Some(8191)));
+        assert_eq!(HeaderValue::parse(&HeaderCode::GPS, "uBLOXf_TIM-LP,16,max9000m"),
+                   sensor(None, Some("uBLOXf_TIM-LP"), Some(16), Some(9000)));
+        assert_eq!(HeaderValue::parse(&HeaderCode::GPS, "uBLOX,LEA-4P,16,max9000m"),
+                   sensor(Some("uBLOX"), Some("LEA-4P"), Some(16), Some(9000)));
+        assert_eq!(HeaderValue::parse(&HeaderCode::GPS, "FDK/GSU-15D"),
+                   sensor(None, Some("FDK/GSU-15D"), None, None));
+        assert_eq!(HeaderValue::parse(&HeaderCode::PRS, "Intersema MS5534B,8191"),
+                   sensor(Some("Intersema"), Some("MS5534B"), None, Some(8191)));
+        assert_eq!(HeaderValue::parse(&HeaderCode::PRS, "INTERSEMA,MS5534A,max8000m"),
+                   sensor(Some("INTERSEMA"), Some("MS5534A"), None, Some(8000)));
+        assert_eq!(HeaderValue::parse(&HeaderCode::PRS, "Bosch,BMP280,30000ft"),
+                   sensor(Some("Bosch"), Some("BMP280"), None, Some(9144)));
+        assert_eq!(HeaderValue::parse(&HeaderCode::PRS, ""), HeaderValue::Text("".into()));
+    }
+
+    #[test]
+    fn test_time_zone() {
+        assert_eq!(HeaderValue::parse(&HeaderCode::TZN, "+2"), HeaderValue::TimeZone(UtcOffset::from_minutes(120)));
+        assert_eq!(HeaderValue::parse(&HeaderCode::TZN, "-3.50"), HeaderValue::TimeZone(UtcOffset::from_minutes(-210)));
+        assert_eq!(HeaderValue::parse(&HeaderCode::TZN, "+05:30"), HeaderValue::TimeZone(UtcOffset::from_minutes(330)));
+        assert_eq!(HeaderValue::parse(&HeaderCode::TZN, "UTC+10"), HeaderValue::TimeZone(UtcOffset::from_minutes(600)));
+        assert_eq!(HeaderValue::parse(&HeaderCode::TZN, "Europe/Berlin"), HeaderValue::Text("Europe/Berlin".into()));
+        assert_eq!(HeaderValue::parse(&HeaderCode::TZN, "+25"), HeaderValue::Text("+25".into()));
+        assert_relative_eq!(UtcOffset::from_minutes(-210).hours(), -3.5);
+    }
+
+    #[test]
+    fn test_fr_type() {
+        assert_eq!(HeaderValue::parse(&HeaderCode::FTY, "Flarm-IGC"),
+                   HeaderValue::FrType { manufacturer: "Flarm-IGC".into(), model: None });
+        assert_eq!(HeaderValue::parse(&HeaderCode::FTY, "LXNAVIGATION,LX7007F"),
+                   HeaderValue::FrType { manufacturer: "LXNAVIGATION".into(), model: Some("LX7007F".into()) });
+        assert_eq!(HeaderValue::parse(&HeaderCode::FTY, "LK8000 PNA GENERIC"),
+                   HeaderValue::FrType { manufacturer: "LK8000".into(), model: Some("PNA GENERIC".into()) });
+        assert_eq!(HeaderValue::parse(&HeaderCode::FTY, ""), HeaderValue::Text("".into()));
+    }
+
+    #[test]
+    fn test_gnss_receivers() {
+        assert_eq!(HeaderValue::parse(&HeaderCode::GLO, "uBLOX,NEO-M8N,72,max18000m"),
+                   sensor(Some("uBLOX"), Some("NEO-M8N"), Some(72), Some(18000)));
+        assert_eq!(HeaderValue::parse(&HeaderCode::GAL, "uBLOX,NEO-M8N,72,max18000m"),
+                   sensor(Some("uBLOX"), Some("NEO-M8N"), Some(72), Some(18000)));
+        assert_eq!(HeaderValue::parse(&HeaderCode::BEI, "uBLOX,NEO-M8N,72,max18000m"),
+                   sensor(Some("uBLOX"), Some("NEO-M8N"), Some(72), Some(18000)));
+    }
+
+    #[test]
+    fn test_date_of_birth() {
+        assert_eq!(HeaderValue::parse(&HeaderCode::DB1, "230376"), HeaderValue::DateOfBirth(Date::new(76, 3, 23)));
+        assert_eq!(HeaderValue::parse(&HeaderCode::DB2, "010199"), HeaderValue::DateOfBirth(Date::new(99, 1, 1)));
+        assert_eq!(HeaderValue::parse(&HeaderCode::DB3, "010199"), HeaderValue::DateOfBirth(Date::new(99, 1, 1)));
+        assert_eq!(HeaderValue::parse(&HeaderCode::DOB, "230376"), HeaderValue::DateOfBirth(Date::new(76, 3, 23)));
+        assert_eq!(HeaderValue::parse(&HeaderCode::DB1, "23.03.1976"), HeaderValue::Text("23.03.1976".into()));
+    }
+
+    #[test]
+    fn test_altimeter_setting() {
+        assert_eq!(HeaderValue::parse(&HeaderCode::ATS, "101325"), HeaderValue::AltimeterSetting(1013.25));
+        assert_eq!(HeaderValue::parse(&HeaderCode::ATS, "098075"), HeaderValue::AltimeterSetting(980.75));
+        assert_eq!(HeaderValue::parse(&HeaderCode::ATS, "1013"), HeaderValue::Text("1013".into()));
+    }
+
+    #[test]
+    fn test_altitude_references() {
+        assert_eq!(HeaderValue::parse(&HeaderCode::ALG, "GEO"), HeaderValue::GnssAltitude(GnssAltitudeReference::Geoid));
+        assert_eq!(HeaderValue::parse(&HeaderCode::ALG, "ELL"), HeaderValue::GnssAltitude(GnssAltitudeReference::Ellipsoid));
+        assert_eq!(HeaderValue::parse(&HeaderCode::ALG, "NIL"), HeaderValue::GnssAltitude(GnssAltitudeReference::NotRecorded));
+        assert_eq!(HeaderValue::parse(&HeaderCode::ALG, "ISA"), HeaderValue::Text("ISA".into()));
+        assert_eq!(HeaderValue::parse(&HeaderCode::ALP, "ISA"), HeaderValue::PressureAltitude(PressureAltitudeReference::ISA));
+        assert_eq!(HeaderValue::parse(&HeaderCode::ALP, "MSL"), HeaderValue::PressureAltitude(PressureAltitudeReference::MSL));
+        assert_eq!(HeaderValue::parse(&HeaderCode::ALP, "NKN"), HeaderValue::PressureAltitude(PressureAltitudeReference::NotKnown));
+        assert_eq!(HeaderValue::parse(&HeaderCode::ALP, "GEO"), HeaderValue::Text("GEO".into()));
+    }
+
+    #[test]
+    fn test_software() {
+        assert_eq!(HeaderValue::parse(&HeaderCode::SOF, "GpsDump,4.53,1907102039"), HeaderValue::Software {
+            name: "GpsDump".into(),
+            version: Some("4.53".into()),
+            downloaded: Some(DateTime::new(Date::new(10, 7, 19), Time::from_hms(20, 39, 0))),
+        });
+        assert_eq!(HeaderValue::parse(&HeaderCode::SOF, "GpsDump"), HeaderValue::Software {
+            name: "GpsDump".into(),
+            version: None,
+            downloaded: None,
+        });
+        assert_eq!(HeaderValue::parse(&HeaderCode::SOF, "GpsDump,4.53,yesterday"),
+                   HeaderValue::Text("GpsDump,4.53,yesterday".into()));
+        assert_eq!(HeaderValue::parse(&HeaderCode::SOF, "GpsDump,4.53,1907102460"),
+                   HeaderValue::Text("GpsDump,4.53,1907102460".into()));
+        assert_eq!(HeaderValue::parse(&HeaderCode::SOF, "GpsDump,4.53,1907109900"),
+                   HeaderValue::Text("GpsDump,4.53,1907109900".into()));
+    }
+
+    #[test]
+    fn test_security() {
+        assert_eq!(HeaderValue::parse(&HeaderCode::FRS, "Security Micro Operated FR re-set needed"),
+                   HeaderValue::SecuritySuspect(Some("Security Micro Operated FR re-set needed".into())));
+        assert_eq!(HeaderValue::parse(&HeaderCode::FRS, ""), HeaderValue::SecuritySuspect(None));
+        assert_eq!(HeaderValue::parse(&HeaderCode::FRS, "SECSUSPECTUSEVALIPROG"), HeaderValue::SecuritySuspect(None));
+
+        let record = HRecord::parse(b"HFFRSSECSUSPECTUSEVALIPROG:Security Micro Operated").unwrap();
+        assert_eq!(record.value(), HeaderValue::SecuritySuspect(Some("Security Micro Operated".into())));
+        let record = HRecord::parse(b"HFFRSSECURITY:").unwrap();
+        assert_eq!(record.value(), HeaderValue::SecuritySuspect(None));
+    }
+
+    #[test]
+    fn test_units() {
+        assert_eq!(HeaderValue::parse(&HeaderCode::UNT, "m,km/h,m/s"),
+                   HeaderValue::Units(vec![Unit::Metres, Unit::KilometresPerHour, Unit::MetresPerSecond]));
+        assert_eq!(HeaderValue::parse(&HeaderCode::UNT, "ft kt FPM hPa"),
+                   HeaderValue::Units(vec![Unit::Feet, Unit::Knots, Unit::FeetPerMinute, Unit::Hectopascals]));
+        assert_eq!(HeaderValue::parse(&HeaderCode::UNT, "km, furlongs"),
+                   HeaderValue::Units(vec![Unit::Kilometres, Unit::Other("furlongs".into())]));
+        assert_eq!(HeaderValue::parse(&HeaderCode::UNT, " , "), HeaderValue::Text(" , ".into()));
+    }
+
+    #[test]
+    fn test_other() {
+        assert_eq!(HeaderValue::parse(&HeaderCode::PLT, "John Doe"), HeaderValue::Text("John Doe".into()));
+        assert_eq!(HeaderValue::parse(&HeaderCode::SIT, "Lasham UK"), HeaderValue::Text("Lasham UK".into()));
+    }
+
+    proptest! {
+        #[test]
+        #[allow(unused_must_use)]
+        fn parse_doesnt_crash(s in r"\PC*") {
+            for code in &[HeaderCode::DTE, HeaderCode::FXA, HeaderCode::DTM, HeaderCode::GPS,
+                          HeaderCode::PRS, HeaderCode::TZN, HeaderCode::FTY, HeaderCode::DB1,
+                          HeaderCode::ATS, HeaderCode::ALG, HeaderCode::ALP, HeaderCode::SOF,
+                          HeaderCode::FRS, HeaderCode::UNT] {
+                HeaderValue::parse(code, &s);
+            }
+        }
+    }
+}
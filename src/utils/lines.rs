@@ -1,37 +1,249 @@
 use std::io::{Read, BufRead, BufReader, Cursor, Result};
+#[cfg(feature = "tokio")]
+use std::io::{Error, ErrorKind};
+#[cfg(feature = "tokio")]
+use std::mem;
+#[cfg(feature = "tokio")]
+use std::pin::Pin;
+#[cfg(feature = "tokio")]
+use std::task::{Context, Poll};
+
+#[cfg(feature = "tokio")]
+use futures_core::Stream;
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncBufRead, AsyncRead};
 
 pub trait ByteLinesExt: BufRead {
     fn byte_lines(self) -> ByteLines<Self> where Self: Sized {
-        ByteLines { buf: self }
+        ByteLines::new(self)
     }
 }
 
 impl<R: Read> ByteLinesExt for BufReader<R> {}
 impl<T> ByteLinesExt for Cursor<T> where T: AsRef<[u8]> {}
+impl<B: BufRead + ?Sized> ByteLinesExt for Box<B> {}
+
+/// Line terminator that followed a line in the file
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LineEnding {
+    /// `\r\n`, as required by the specification
+    CrLf,
+    /// `\n` only
+    Lf,
+    /// Last line of a file that does not end with a line break
+    None,
+}
+
+impl LineEnding {
+    pub fn as_bytes(&self) -> &'static [u8] {
+        match *self {
+            LineEnding::CrLf => b"\r\n",
+            LineEnding::Lf => b"\n",
+            LineEnding::None => b"",
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct ByteLines<B> {
     buf: B,
+    line: Vec<u8>,
+}
+
+impl<B: BufRead> ByteLines<B> {
+    pub fn new(buf: B) -> ByteLines<B> {
+        ByteLines { buf, line: Vec::new() }
+    }
+
+    /// Reads the next line and returns it together with the line ending that
+    /// was stripped from it.
+    pub fn next_with_ending(&mut self) -> Option<Result<(Vec<u8>, LineEnding)>> {
+        self.next_line().map(|result| result.map(|(line, line_ending)| (line.to_vec(), line_ending)))
+    }
+
+    /// Like `next_with_ending()`, but reads the line into a buffer that is
+    /// reused for every line, so that no allocations are needed once the
+    /// buffer is large enough for the longest line.
+    pub fn next_line(&mut self) -> Option<Result<(&[u8], LineEnding)>> {
+        self.line.clear();
+        match self.buf.read_until(b'\n', &mut self.line) {
+            Ok(0) => None,
+            Ok(_n) => {
+                let line_ending = strip_line_ending(&mut self.line);
+                Some(Ok((&self.line, line_ending)))
+            }
+            Err(e) => Some(Err(e))
+        }
+    }
 }
 
 impl<B: BufRead> Iterator for ByteLines<B> {
     type Item = Result<Vec<u8>>;
 
     fn next(&mut self) -> Option<Result<Vec<u8>>> {
-        let mut buf = Vec::new();
-        match self.buf.read_until(b'\n', &mut buf) {
-            Ok(0) => None,
-            Ok(_n) => {
-                if buf.ends_with(b"\n") {
-                    buf.pop();
-                    if buf.ends_with(b"\r") {
-                        buf.pop();
+        self.next_with_ending().map(|result| result.map(|(line, _)| line))
+    }
+}
+
+/// Same as `ByteLines`, for a file that is already in memory. The lines are
+/// borrowed from the bytes instead of being copied into a buffer.
+#[derive(Debug, Clone)]
+pub struct SliceLines<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> SliceLines<'a> {
+    pub fn new(bytes: &'a [u8]) -> SliceLines<'a> {
+        SliceLines { bytes }
+    }
+}
+
+impl<'a> Iterator for SliceLines<'a> {
+    type Item = (&'a [u8], LineEnding);
+
+    /// Returns the next line together with the line ending that was
+    /// stripped from it.
+    fn next(&mut self) -> Option<(&'a [u8], LineEnding)> {
+        if self.bytes.is_empty() {
+            return None;
+        }
+
+        let end = self.bytes.iter().position(|b| *b == b'\n').map_or(self.bytes.len(), |end| end + 1);
+        let (line, rest) = self.bytes.split_at(end);
+        self.bytes = rest;
+        Some(split_line_ending(line))
+    }
+}
+
+/// Same as `ByteLinesExt`, for asynchronous sources
+#[cfg(feature = "tokio")]
+pub trait AsyncByteLinesExt: AsyncBufRead {
+    fn byte_lines(self) -> AsyncByteLines<Self> where Self: Sized + Unpin {
+        AsyncByteLines::new(self)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R: AsyncRead> AsyncByteLinesExt for tokio::io::BufReader<R> {}
+#[cfg(feature = "tokio")]
+impl AsyncByteLinesExt for &[u8] {}
+
+/// Default for `AsyncByteLines::max_line_length()`
+#[cfg(feature = "tokio")]
+pub const DEFAULT_MAX_LINE_LENGTH: usize = 64 * 1024;
+
+/// Same as `ByteLines`, but reads from an `AsyncBufRead` and yields the lines
+/// as a `Stream`.
+///
+/// Only the current line is buffered, the rest of the input is read as
+/// the stream is polled. Lines that are longer than `max_line_length()` are
+/// returned as an error instead, so that a peer can't make the buffer grow
+/// without bounds.
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub struct AsyncByteLines<B> {
+    buf: B,
+    line: Vec<u8>,
+    max_line_length: usize,
+    /// Whether the rest of a line that was too long is skipped
+    is_discarding: bool,
+}
+
+/// Line and the line ending that was stripped from it
+#[cfg(feature = "tokio")]
+type LineWithEnding = (Vec<u8>, LineEnding);
+
+#[cfg(feature = "tokio")]
+impl<B: AsyncBufRead + Unpin> AsyncByteLines<B> {
+    pub fn new(buf: B) -> AsyncByteLines<B> {
+        AsyncByteLines { buf, line: Vec::new(), max_line_length: DEFAULT_MAX_LINE_LENGTH, is_discarding: false }
+    }
+
+    /// Limits the length of the lines, including their line ending. A longer
+    /// line is returned as an `InvalidData` error and the stream continues
+    /// with the line after it. The default is `DEFAULT_MAX_LINE_LENGTH`.
+    pub fn max_line_length(mut self, limit: usize) -> AsyncByteLines<B> {
+        self.max_line_length = limit;
+        self
+    }
+
+    /// Polls for the next line and returns it together with the line ending
+    /// that was stripped from it.
+    pub fn poll_next_with_ending(&mut self, cx: &mut Context) -> Poll<Option<Result<LineWithEnding>>> {
+        loop {
+            let (consumed, found_end, is_too_long) = {
+                let available = match Pin::new(&mut self.buf).poll_fill_buf(cx) {
+                    Poll::Ready(Ok(available)) => available,
+                    Poll::Ready(Err(error)) => return Poll::Ready(Some(Err(error))),
+                    Poll::Pending => return Poll::Pending,
+                };
+
+                // end of file
+                if available.is_empty() {
+                    if self.line.is_empty() {
+                        return Poll::Ready(None);
                     }
+                    break;
                 }
-                Some(Ok(buf))
+
+                let (length, found_end) = match available.iter().position(|b| *b == b'\n') {
+                    Some(end) => (end + 1, true),
+                    None => (available.len(), false),
+                };
+
+                let is_too_long = self.line.len() + length > self.max_line_length;
+                if !self.is_discarding && !is_too_long {
+                    self.line.extend_from_slice(&available[..length]);
+                }
+                (length, found_end, is_too_long)
+            };
+
+            Pin::new(&mut self.buf).consume(consumed);
+            if self.is_discarding {
+                self.is_discarding = !found_end;
+            } else if is_too_long {
+                self.line.clear();
+                self.is_discarding = !found_end;
+                let message = format!("line is longer than {} bytes", self.max_line_length);
+                return Poll::Ready(Some(Err(Error::new(ErrorKind::InvalidData, message))));
+            } else if found_end {
+                break;
             }
-            Err(e) => Some(Err(e))
         }
+
+        let mut line = mem::take(&mut self.line);
+        let line_ending = strip_line_ending(&mut line);
+        Poll::Ready(Some(Ok((line, line_ending))))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<B: AsyncBufRead + Unpin> Stream for AsyncByteLines<B> {
+    type Item = Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Result<Vec<u8>>>> {
+        self.get_mut().poll_next_with_ending(cx).map(|line| line.map(|result| result.map(|(line, _)| line)))
+    }
+}
+
+/// Removes the `\n` or `\r\n` from the end of the line.
+fn strip_line_ending(line: &mut Vec<u8>) -> LineEnding {
+    let (len, line_ending) = {
+        let (stripped, line_ending) = split_line_ending(line);
+        (stripped.len(), line_ending)
+    };
+    line.truncate(len);
+    line_ending
+}
+
+/// Splits the `\n` or `\r\n` from the end of the line.
+fn split_line_ending(line: &[u8]) -> (&[u8], LineEnding) {
+    if let Some(line) = line.strip_suffix(b"\r\n") {
+        (line, LineEnding::CrLf)
+    } else if let Some(line) = line.strip_suffix(b"\n") {
+        (line, LineEnding::Lf)
+    } else {
+        (line, LineEnding::None)
     }
 }
 
@@ -67,4 +279,83 @@ mod tests {
         assert_eq!(s.next().unwrap().unwrap(), b"def");
         assert!(s.next().is_none());
     }
+
+    #[test]
+    fn line_endings() {
+        let mut s = Cursor::new(b"abc\r\ndef\n\r\nghi\r").byte_lines();
+        assert_eq!(s.next_with_ending().unwrap().unwrap(), (b"abc".to_vec(), LineEnding::CrLf));
+        assert_eq!(s.next_with_ending().unwrap().unwrap(), (b"def".to_vec(), LineEnding::Lf));
+        assert_eq!(s.next_with_ending().unwrap().unwrap(), (b"".to_vec(), LineEnding::CrLf));
+        assert_eq!(s.next_with_ending().unwrap().unwrap(), (b"ghi\r".to_vec(), LineEnding::None));
+        assert!(s.next_with_ending().is_none());
+    }
+
+    #[test]
+    fn reused_buffer() {
+        let mut s = Cursor::new(b"abcdef\r\nghi\n").byte_lines();
+        assert_eq!(s.next_line().unwrap().unwrap(), (&b"abcdef"[..], LineEnding::CrLf));
+        assert_eq!(s.next_line().unwrap().unwrap(), (&b"ghi"[..], LineEnding::Lf));
+        assert!(s.next_line().is_none());
+    }
+
+    #[test]
+    fn slice_lines() {
+        let lines: Vec<_> = SliceLines::new(b"abc\r\ndef\n\r\nghi\r").collect();
+        assert_eq!(lines, vec![
+            (&b"abc"[..], LineEnding::CrLf),
+            (&b"def"[..], LineEnding::Lf),
+            (&b""[..], LineEnding::CrLf),
+            (&b"ghi\r"[..], LineEnding::None),
+        ]);
+        assert_eq!(SliceLines::new(b"").count(), 0);
+        assert_eq!(SliceLines::new(b"\n").count(), 1);
+    }
+
+    proptest! {
+        #[test]
+        fn slice_lines_same_as_byte_lines(s in r"[a\r\n]*") {
+            let mut expected = Cursor::new(s.as_bytes()).byte_lines();
+            for (line, line_ending) in SliceLines::new(s.as_bytes()) {
+                let (expected_line, expected_ending) = expected.next_line().unwrap().unwrap();
+                prop_assert_eq!(line, expected_line);
+                prop_assert_eq!(line_ending, expected_ending);
+            }
+            prop_assert!(expected.next_line().is_none());
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn async_line_endings() {
+        use std::future::poll_fn;
+
+        let mut s = AsyncByteLinesExt::byte_lines(&b"abc\r\ndef\n\r\nghi\r"[..]);
+        let mut next = || tokio::runtime::Builder::new_current_thread().build().unwrap()
+            .block_on(poll_fn(|cx| s.poll_next_with_ending(cx)))
+            .map(Result::unwrap);
+
+        assert_eq!(next(), Some((b"abc".to_vec(), LineEnding::CrLf)));
+        assert_eq!(next(), Some((b"def".to_vec(), LineEnding::Lf)));
+        assert_eq!(next(), Some((b"".to_vec(), LineEnding::CrLf)));
+        assert_eq!(next(), Some((b"ghi\r".to_vec(), LineEnding::None)));
+        assert_eq!(next(), None);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn async_max_line_length() {
+        use std::future::poll_fn;
+
+        let input = b"abc\r\ndefghijk\nlmn\nopqrstuvwxyz";
+        let mut s = AsyncByteLinesExt::byte_lines(tokio::io::BufReader::with_capacity(4, &input[..])).max_line_length(5);
+        let mut next = || tokio::runtime::Builder::new_current_thread().build().unwrap()
+            .block_on(poll_fn(|cx| s.poll_next_with_ending(cx)))
+            .map(|result| result.map_err(|error| (error.kind(), error.to_string())));
+
+        assert_eq!(next(), Some(Ok((b"abc".to_vec(), LineEnding::CrLf))));
+        assert_eq!(next(), Some(Err((ErrorKind::InvalidData, "line is longer than 5 bytes".to_string()))));
+        assert_eq!(next(), Some(Ok((b"lmn".to_vec(), LineEnding::Lf))));
+        assert!(next().unwrap().is_err());
+        assert_eq!(next(), None);
+    }
 }
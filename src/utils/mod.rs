@@ -1,7 +1,9 @@
 pub mod additions;
 pub mod coordinates;
 pub mod events;
+pub mod fields;
 pub mod headers;
+#[cfg(feature = "std")]
 pub mod lines;
 pub mod num;
 pub mod text;
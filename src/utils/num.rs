@@ -4,7 +4,31 @@ pub fn parse_int<T: FromStr>(bytes: &[u8]) -> Option<T> {
     // `unsafe` here should be okay because `from_str()` converts back
     // to `&[u8]` and only cares about ASCII digits
     let chars = unsafe { from_utf8_unchecked(bytes) };
-    <(T)>::from_str(chars).ok()
+    T::from_str(chars).ok()
+}
+
+/// Rounds half-way cases away from zero, like `f64::round()`, which is not
+/// available without `std`.
+#[cfg(feature = "std")]
+pub fn round(value: f64) -> f64 {
+    value.round()
+}
+
+/// Rounds half-way cases away from zero, like `f64::round()`, which is not
+/// available without `std`.
+///
+/// Only works for values that fit into an `i64`.
+#[cfg(not(feature = "std"))]
+pub fn round(value: f64) -> f64 {
+    let truncated = value as i64 as f64;
+    let fraction = value - truncated;
+    if fraction >= 0.5 {
+        truncated + 1.
+    } else if fraction <= -0.5 {
+        truncated - 1.
+    } else {
+        truncated
+    }
 }
 
 #[cfg(test)]
@@ -26,6 +50,16 @@ mod tests {
         assert_eq!(parse_int::<i32>(b"-0d18"), None);
     }
 
+    #[test]
+    fn test_round() {
+        assert_eq!(round(0.), 0.);
+        assert_eq!(round(0.49), 0.);
+        assert_eq!(round(0.5), 1.);
+        assert_eq!(round(-0.5), -1.);
+        assert_eq!(round(-1.49), -1.);
+        assert_eq!(round(2.5), 3.);
+    }
+
     proptest! {
         #[test]
         fn test_parse_int_with_u8(v: u8) {
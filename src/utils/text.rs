@@ -1,12 +1,95 @@
-use encoding::{DecoderTrap, Encoding};
-use encoding::all::{UTF_8, ISO_8859_1};
+use std::str;
+
+use ::{Error, ErrorKind, Result};
+use ::prelude::*;
+
+/// Character encoding of the text fields of an IGC file
+///
+/// The specification only allows ASCII, but many recorders write names of
+/// pilots and places in the encoding of the operating system they run on.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    /// ISO 8859-1, used by many western European recorders
+    Latin1,
+    /// Central European Windows code page, used by e.g. Czech and Polish
+    /// recorders
+    Windows1250,
+}
+
+impl Encoding {
+    /// Detects the encoding of some text, e.g. of a whole file.
+    ///
+    /// Text that is valid UTF-8 (including plain ASCII) is UTF-8, text with
+    /// letters that only exist in Windows-1250 (like `ł`, `ą`, `š` or `ž`)
+    /// is Windows-1250 and everything else is Latin-1. Many letters have
+    /// the same byte value in both code pages and some are only different
+    /// letters (`ř` is `ø` in Latin-1), so set the encoding explicitly if
+    /// it is known.
+    pub fn detect(bytes: &[u8]) -> Encoding {
+        if str::from_utf8(bytes).is_ok() {
+            Encoding::Utf8
+        } else if bytes.iter().any(|b| is_windows_1250_letter(*b)) {
+            Encoding::Windows1250
+        } else {
+            Encoding::Latin1
+        }
+    }
+
+    /// Decodes the bytes, which only fails for invalid UTF-8, since every
+    /// byte is a character in the other encodings.
+    pub fn decode(&self, bytes: &[u8]) -> Option<String> {
+        match *self {
+            Encoding::Utf8 => str::from_utf8(bytes).ok().map(ToOwned::to_owned),
+            // Latin1 maps every byte to the Unicode code point of the same value
+            Encoding::Latin1 => Some(bytes.iter().map(|b| *b as char).collect()),
+            Encoding::Windows1250 => Some(bytes.iter().map(|b| windows_1250(*b)).collect()),
+        }
+    }
+}
+
+/// Characters of the bytes `0x80` to `0xFF` in Windows-1250. The five bytes
+/// without a character are mapped to the same code point, like in Latin-1.
+const WINDOWS_1250: [char; 128] = [
+    '\u{20ac}', '\u{0081}', '\u{201a}', '\u{0083}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{0088}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{015a}', '\u{0164}', '\u{017d}', '\u{0179}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{0098}', '\u{2122}', '\u{0161}', '\u{203a}', '\u{015b}', '\u{0165}', '\u{017e}', '\u{017a}',
+    '\u{00a0}', '\u{02c7}', '\u{02d8}', '\u{0141}', '\u{00a4}', '\u{0104}', '\u{00a6}', '\u{00a7}',
+    '\u{00a8}', '\u{00a9}', '\u{015e}', '\u{00ab}', '\u{00ac}', '\u{00ad}', '\u{00ae}', '\u{017b}',
+    '\u{00b0}', '\u{00b1}', '\u{02db}', '\u{0142}', '\u{00b4}', '\u{00b5}', '\u{00b6}', '\u{00b7}',
+    '\u{00b8}', '\u{0105}', '\u{015f}', '\u{00bb}', '\u{013d}', '\u{02dd}', '\u{013e}', '\u{017c}',
+    '\u{0154}', '\u{00c1}', '\u{00c2}', '\u{0102}', '\u{00c4}', '\u{0139}', '\u{0106}', '\u{00c7}',
+    '\u{010c}', '\u{00c9}', '\u{0118}', '\u{00cb}', '\u{011a}', '\u{00cd}', '\u{00ce}', '\u{010e}',
+    '\u{0110}', '\u{0143}', '\u{0147}', '\u{00d3}', '\u{00d4}', '\u{0150}', '\u{00d6}', '\u{00d7}',
+    '\u{0158}', '\u{016e}', '\u{00da}', '\u{0170}', '\u{00dc}', '\u{00dd}', '\u{0162}', '\u{00df}',
+    '\u{0155}', '\u{00e1}', '\u{00e2}', '\u{0103}', '\u{00e4}', '\u{013a}', '\u{0107}', '\u{00e7}',
+    '\u{010d}', '\u{00e9}', '\u{0119}', '\u{00eb}', '\u{011b}', '\u{00ed}', '\u{00ee}', '\u{010f}',
+    '\u{0111}', '\u{0144}', '\u{0148}', '\u{00f3}', '\u{00f4}', '\u{0151}', '\u{00f6}', '\u{00f7}',
+    '\u{0159}', '\u{016f}', '\u{00fa}', '\u{0171}', '\u{00fc}', '\u{00fd}', '\u{0163}', '\u{02d9}',
+];
+
+fn windows_1250(byte: u8) -> char {
+    if byte < 0x80 { byte as char } else { WINDOWS_1250[byte as usize - 0x80] }
+}
+
+/// Letters in Windows-1250 that are control characters or symbols in Latin-1
+fn is_windows_1250_letter(byte: u8) -> bool {
+    windows_1250(byte).is_alphabetic() && !(byte as char).is_alphabetic()
+}
 
 /// Parse a byte slice as either UTF8 or Latin1
 pub fn as_text(bytes: &[u8]) -> Option<String> {
-    let bytes = bytes.into();
-    UTF_8.decode(bytes, DecoderTrap::Strict)
-        .or_else(|_| ISO_8859_1.decode(bytes, DecoderTrap::Strict))
-        .ok()
+    Encoding::Utf8.decode(bytes).or_else(|| Encoding::Latin1.decode(bytes))
+}
+
+/// Decodes the bytes `start..end` of a line as text with the `encoding`, or
+/// with `as_text()` if the encoding of the file is not known.
+pub fn text_field(line: &[u8], start: usize, end: usize, field: &str, encoding: Option<Encoding>) -> Result<String> {
+    let bytes = &line[start..end];
+    encoding.map_or_else(|| as_text(bytes), |encoding| encoding.decode(bytes))
+        .ok_or_else(|| Error::parse(ErrorKind::BadEncoding, line).in_field(start + 1, field))
 }
 
 #[cfg(test)]
@@ -20,6 +103,35 @@ mod tests {
         assert_eq!(as_text(b"J\xf6rg M\xfcller").unwrap(), "Jörg Müller");
     }
 
+    #[test]
+    fn test_detect() {
+        assert_eq!(Encoding::detect(b"John Doe"), Encoding::Utf8);
+        assert_eq!(Encoding::detect("Jörg Müller".as_bytes()), Encoding::Utf8);
+        assert_eq!(Encoding::detect(b"J\xf6rg M\xfcller"), Encoding::Latin1);
+        assert_eq!(Encoding::detect(b"Pawe\xb3 \xa3uczak"), Encoding::Windows1250);
+        assert_eq!(Encoding::detect(b"Ji\xf8\xed \x8aediv\xfd"), Encoding::Windows1250);
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(Encoding::Utf8.decode("Jörg".as_bytes()).unwrap(), "Jörg");
+        assert_eq!(Encoding::Utf8.decode(b"J\xf6rg"), None);
+        assert_eq!(Encoding::Latin1.decode(b"J\xf6rg").unwrap(), "Jörg");
+        assert_eq!(Encoding::Windows1250.decode(b"Pawe\xb3 \xa3uczak").unwrap(), "Paweł Łuczak");
+        assert_eq!(Encoding::Windows1250.decode(b"Ji\xf8\xed \x8aediv\xfd").unwrap(), "Jiří Šedivý");
+        assert_eq!(Encoding::Windows1250.decode(b"\x81\xe4").unwrap(), "\u{81}ä");
+    }
+
+    #[test]
+    fn test_text_field() {
+        let line = b"HFPLTPILOT:Ji\xf8\xed";
+        assert_eq!(text_field(line, 11, line.len(), "text", None).unwrap(), "Jiøí");
+        assert_eq!(text_field(line, 11, line.len(), "text", Some(Encoding::Windows1250)).unwrap(), "Jiří");
+
+        let error = text_field(line, 11, line.len(), "text", Some(Encoding::Utf8)).unwrap_err();
+        assert_eq!(error.kind(), Some(ErrorKind::BadEncoding));
+    }
+
     proptest! {
         #[test]
         #[allow(unused_must_use)]
@@ -0,0 +1,238 @@
+use ::{Error, ErrorKind, Record, Date, Time};
+use ::utils::additions::AdditionDef;
+use ::utils::headers::{HeaderCode, HeaderSource, HeaderValue};
+use ::utils::num::parse_int;
+use ::utils::text::Encoding;
+use ::prelude::*;
+
+/// How much of the IGC specification is enforced while reading a file
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum ParseMode {
+    /// Rejects every line that violates the AL4a specification, e.g. times
+    /// with `hour > 23`, coordinates with 60 or more minutes, or I records
+    /// with a wrong number of additions
+    Strict,
+
+    /// Rejects lines that can not be parsed, but does not validate the values
+    #[default]
+    Standard,
+
+    /// Recovers as much as possible and reports everything else as warnings
+    /// instead of errors. Lines that can't be parsed at all are skipped.
+    Lenient,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct ParseOptions {
+    pub mode: ParseMode,
+
+    /// Encoding of the text fields, or `None` to detect it from the file
+    pub encoding: Option<Encoding>,
+}
+
+impl ParseOptions {
+    pub fn strict() -> ParseOptions {
+        ParseOptions { mode: ParseMode::Strict, ..ParseOptions::default() }
+    }
+
+    pub fn lenient() -> ParseOptions {
+        ParseOptions { mode: ParseMode::Lenient, ..ParseOptions::default() }
+    }
+
+    /// Decode all text fields with the given `encoding` instead of
+    /// detecting it.
+    pub fn encoding(mut self, encoding: Encoding) -> ParseOptions {
+        self.encoding = Some(encoding);
+        self
+    }
+}
+
+/// Checks a successfully parsed line against the rules of the specification
+/// that the parsers don't enforce themselves and returns all violations.
+pub fn validate(line: &[u8], record: &Record) -> Vec<Error> {
+    let mut errors = Vec::new();
+
+    {
+        let mut check = |result: Option<Error>| errors.extend(result);
+
+        match *record {
+            Record::B(_) => {
+                check(check_time(line, 1, "time"));
+                check(check_latitude(line, 7));
+                check(check_longitude(line, 15));
+            },
+            Record::C(_) if line.get(8).is_some_and(|b| *b == b'N' || *b == b'S') => {
+                check(check_latitude(line, 1));
+                check(check_longitude(line, 9));
+            },
+            Record::C(_) => {
+                check(check_date(line, 1, "date"));
+                check(check_time(line, 7, "time"));
+                if &line[13..19] != b"000000" {
+                    check(check_date(line, 13, "flight date"));
+                }
+            },
+            Record::D(_) => if line.len() != 6 || !line[2..].iter().all(u8::is_ascii_alphanumeric) {
+                check(Some(invalid(line, 3, "dgps station id")));
+            },
+            Record::E(_) | Record::K(_) => check(check_time(line, 1, "time")),
+            Record::F(_) => {
+                check(check_time(line, 1, "time"));
+                if line.len() % 2 == 0 {
+                    check(Some(invalid(line, line.len(), "satellite ids")));
+                }
+            },
+            Record::H(ref record) => {
+                if let HeaderSource::Other(_) = record.source {
+                    check(Some(invalid(line, 2, "data source")));
+                }
+
+                let long_name = line[5..].iter().position(|b| *b == b':').map(|end| &line[5..5 + end]);
+                if let Some(long_name) = long_name {
+                    if !long_name.iter().all(|b| b.is_ascii_alphanumeric() || *b == b' ') {
+                        check(Some(invalid(line, 6, "long name")));
+                    }
+                }
+
+                if record.code == HeaderCode::DTE {
+                    match record.value() {
+                        HeaderValue::Date { date, .. } if date.is_valid() => {},
+                        _ => check(Some(Error::parse(ErrorKind::BadDate, line).in_field(6, "date"))),
+                    }
+                }
+            },
+            Record::I(ref record) => {
+                check(check_addition_defs(line, record.num_additions, &record.addition_defs, 36));
+            },
+            Record::J(ref record) => {
+                check(check_addition_defs(line, record.num_additions, &record.addition_defs, 8));
+            },
+            Record::A(_) | Record::G(_) | Record::L(_) => {},
+        }
+    }
+
+    errors
+}
+
+fn invalid(line: &[u8], column: usize, field: &str) -> Error {
+    Error::invalid_record(line).in_field(column, field)
+}
+
+fn check_time(line: &[u8], start: usize, field: &str) -> Option<Error> {
+    if Time::parse_unchecked(&line[start..start + 6]).is_valid() {
+        None
+    } else {
+        Some(Error::parse(ErrorKind::BadTime, line).in_field(start + 1, field))
+    }
+}
+
+fn check_date(line: &[u8], start: usize, field: &str) -> Option<Error> {
+    if Date::parse_unchecked(&line[start..start + 6]).is_valid() {
+        None
+    } else {
+        Some(Error::parse(ErrorKind::BadDate, line).in_field(start + 1, field))
+    }
+}
+
+fn check_latitude(line: &[u8], start: usize) -> Option<Error> {
+    check_coordinate(line, start, 2, 90, "latitude")
+}
+
+fn check_longitude(line: &[u8], start: usize) -> Option<Error> {
+    check_coordinate(line, start, 3, 180, "longitude")
+}
+
+fn check_coordinate(line: &[u8], start: usize, digits: usize, max: u32, field: &str) -> Option<Error> {
+    let degrees: u32 = parse_int(&line[start..start + digits]).unwrap();
+    let milliminutes: u32 = parse_int(&line[start + digits..start + digits + 5]).unwrap();
+
+    if milliminutes < 60000 && (degrees < max || (degrees == max && milliminutes == 0)) {
+        None
+    } else {
+        Some(Error::parse(ErrorKind::BadCoordinate, line).in_field(start + 1, field))
+    }
+}
+
+/// Checks the number of additions and that the byte ranges of the additions
+/// follow each other without overlapping, starting at `first_byte` or later.
+fn check_addition_defs(line: &[u8], num_additions: u8, defs: &[AdditionDef], first_byte: u8) -> Option<Error> {
+    if num_additions as usize != defs.len() || line.len() != 3 + 7 * defs.len() {
+        return Some(Error::parse(ErrorKind::AdditionCountMismatch, line).in_field(2, "number of additions"));
+    }
+
+    let mut next_byte = first_byte;
+    for (i, def) in defs.iter().enumerate() {
+        if def.start_byte < next_byte || def.end_byte < def.start_byte {
+            return Some(Error::parse(ErrorKind::AdditionOutOfRange, line).in_field(4 + 7 * i, def.code.as_str()));
+        }
+        next_byte = def.end_byte.saturating_add(1);
+    }
+
+    None
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(line: &[u8]) -> Vec<Option<ErrorKind>> {
+        let record = Record::parse(line).unwrap();
+        validate(line, &record).iter().map(Error::kind).collect()
+    }
+
+    #[test]
+    fn test_valid_lines() {
+        let lines: &[&[u8]] = &[
+            b"AFLA6NG",
+            b"B1414065016925N00953112EA021640228700309",
+            b"B0000009000000S18000000WV-001200000",
+            b"C040516072924000000000002Task",
+            b"C0000000N00000000ET",
+            b"D20001",
+            b"E104533PEV",
+            b"F1234560102ABC342",
+            b"HFDTEDATE:040516,01",
+            b"HFDTE040516",
+            b"HFDTM100GPSDatum:WGS84",
+            b"I023638FXA3941ENL",
+            b"J010810HDT",
+            b"K160310090",
+            b"LPLTThis flight was my second 1000km attempt",
+        ];
+        for line in lines {
+            assert_eq!(kinds(line), vec![], "{:?}", ::std::str::from_utf8(line));
+        }
+    }
+
+    #[test]
+    fn test_invalid_lines() {
+        assert_eq!(kinds(b"B2414065016925N00953112EA0216402287"), vec![Some(ErrorKind::BadTime)]);
+        assert_eq!(kinds(b"B1414065060000N18000001EA0216402287"),
+                   vec![Some(ErrorKind::BadCoordinate), Some(ErrorKind::BadCoordinate)]);
+        assert_eq!(kinds(b"C310216072960000000000002Task"), vec![Some(ErrorKind::BadDate), Some(ErrorKind::BadTime)]);
+        assert_eq!(kinds(b"C9100000N00000000ET"), vec![Some(ErrorKind::BadCoordinate)]);
+        assert_eq!(kinds(b"D2abc"), vec![Some(ErrorKind::InvalidRecord)]);
+        assert_eq!(kinds(b"D20001x"), vec![Some(ErrorKind::InvalidRecord)]);
+        assert_eq!(kinds(b"E999999PEV"), vec![Some(ErrorKind::BadTime)]);
+        assert_eq!(kinds(b"F12345601023"), vec![Some(ErrorKind::InvalidRecord)]);
+        assert_eq!(kinds(b"HPGTYGliderType:A350"), vec![Some(ErrorKind::InvalidRecord)]);
+        assert_eq!(kinds(b"HFGPSu-blox:LEA-4P,16,8191"), vec![Some(ErrorKind::InvalidRecord)]);
+        assert_eq!(kinds(b"HFDTEDATE:310216"), vec![Some(ErrorKind::BadDate)]);
+        assert_eq!(kinds(b"I033638FXA3941ENL"), vec![Some(ErrorKind::AdditionCountMismatch)]);
+        assert_eq!(kinds(b"I023638FXA3941ENLX"), vec![Some(ErrorKind::AdditionCountMismatch)]);
+        assert_eq!(kinds(b"I023538FXA3941ENL"), vec![Some(ErrorKind::AdditionOutOfRange)]);
+        assert_eq!(kinds(b"I023638FXA3841ENL"), vec![Some(ErrorKind::AdditionOutOfRange)]);
+        assert_eq!(kinds(b"J010710HDT"), vec![Some(ErrorKind::AdditionOutOfRange)]);
+    }
+
+    proptest! {
+        #[test]
+        #[allow(unused_must_use)]
+        fn validate_doesnt_crash(s in r"\PC*") {
+            if let Ok(record) = Record::parse(s.as_bytes()) {
+                validate(s.as_bytes(), &record);
+            }
+        }
+    }
+}
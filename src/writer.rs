@@ -0,0 +1,310 @@
+use std::fmt;
+use std::io::Write;
+
+use ::{Result, Record, ParsedLine, RawLine, Signer};
+use ::security::ContentFilter;
+use ::utils::additions::AdditionDef;
+use ::utils::lines::LineEnding;
+
+/// Writes records as lines of an IGC file, terminated by `CRLF`.
+///
+/// Like the `Reader`, the writer keeps track of the I and J record addition
+/// definitions, so that the additions of the following B and K records are
+/// written at the byte positions that the definitions describe.
+///
+/// With a `Signer`, the signed part of every line that is written is passed
+/// to the signer, and `write_signature()` appends the G records at the end
+/// of the file.
+pub struct Writer<W> {
+    inner: W,
+    b_addition_defs: Vec<AdditionDef>,
+    k_addition_defs: Vec<AdditionDef>,
+    signer: Option<Box<dyn Signer + Send>>,
+    filter: ContentFilter,
+    /// Whether the last line was written without a line ending
+    is_line_open: bool,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(inner: W) -> Writer<W> {
+        Writer {
+            inner,
+            b_addition_defs: Vec::new(),
+            k_addition_defs: Vec::new(),
+            signer: None,
+            filter: ContentFilter::default(),
+            is_line_open: false,
+        }
+    }
+
+    /// Signs all lines that are written from now on with the `signer`.
+    ///
+    /// G records that are written while signing are left out, since they
+    /// would not match the new signature.
+    pub fn with_signer<S: Signer + Send + 'static>(mut self, signer: S) -> Writer<W> {
+        self.signer = Some(Box::new(signer));
+        self
+    }
+
+    /// Writes a single record.
+    ///
+    /// B record altitudes of zero are written as `-0000`, to keep them
+    /// apart from missing altitudes (`00000`), see `BRecord::to_bytes()`.
+    ///
+    /// Returns an error if a B or K record has additions that don't match
+    /// the definitions of the most recent I or J record.
+    pub fn write(&mut self, record: &Record) -> Result<()> {
+        if self.skip(record) {
+            return Ok(());
+        }
+
+        let mut line = match *record {
+            Record::B(ref record) => record.to_bytes_with_additions(&self.b_addition_defs)?,
+            Record::K(ref record) => record.to_bytes_with_additions(&self.k_addition_defs)?,
+            ref record => record.to_bytes()?,
+        };
+
+        self.update_addition_defs(record);
+        self.sign(&line);
+
+        line.extend_from_slice(b"\r\n");
+        self.inner.write_all(&line)?;
+        self.is_line_open = false;
+        Ok(())
+    }
+
+    /// Writes a line from a `Reader`. If the line still has its raw bytes,
+    /// they are written unchanged with their original line ending, otherwise
+    /// the record is formatted like in `write()`. Lines without a record and
+    /// without raw bytes are not written at all.
+    ///
+    /// Set `raw` to `None` after modifying the record of a line.
+    pub fn write_line(&mut self, line: &ParsedLine) -> Result<()> {
+        match (line.record.as_ref(), line.raw.as_ref()) {
+            (Some(record), None) => self.write(record),
+            (Some(record), Some(_)) if self.skip(record) => Ok(()),
+            (record, Some(raw)) => {
+                if let Some(record) = record {
+                    self.update_addition_defs(record);
+                }
+                self.write_raw(raw)
+            },
+            (None, None) => Ok(()),
+        }
+    }
+
+    /// Writes the original bytes of a line unchanged
+    pub fn write_raw(&mut self, raw: &RawLine) -> Result<()> {
+        self.sign(&raw.bytes);
+        self.inner.write_all(&raw.bytes)?;
+        self.inner.write_all(raw.line_ending.as_bytes())?;
+        self.is_line_open = raw.line_ending == LineEnding::None;
+        Ok(())
+    }
+
+    /// Appends the G records with the signature of all lines that were
+    /// written so far. Does nothing if the writer has no `Signer`.
+    pub fn write_signature(&mut self) -> Result<()> {
+        let mut signer = match self.signer.take() {
+            Some(signer) => signer,
+            None => return Ok(()),
+        };
+
+        if self.is_line_open {
+            self.inner.write_all(b"\r\n")?;
+            self.is_line_open = false;
+        }
+
+        let signature = signer.finish();
+        for text in signature.as_bytes().chunks(signer.line_length().max(1)) {
+            self.inner.write_all(b"G")?;
+            self.inner.write_all(text)?;
+            self.inner.write_all(b"\r\n")?;
+        }
+        Ok(())
+    }
+
+    /// Old G records are not written while signing.
+    fn skip(&self, record: &Record) -> bool {
+        self.signer.is_some() && matches!(*record, Record::G(_))
+    }
+
+    fn sign(&mut self, line: &[u8]) {
+        if let Some(ref mut signer) = self.signer {
+            if let Some(signed) = self.filter.signed_bytes(line) {
+                signer.update(&signed);
+            }
+        }
+    }
+
+    fn update_addition_defs(&mut self, record: &Record) {
+        match *record {
+            Record::I(ref record) => self.b_addition_defs = record.addition_defs.clone(),
+            Record::J(ref record) => self.k_addition_defs = record.addition_defs.clone(),
+            _ => {},
+        }
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.inner.flush()?;
+        Ok(())
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: fmt::Debug> fmt::Debug for Writer<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Writer")
+            .field("inner", &self.inner)
+            .field("b_addition_defs", &self.b_addition_defs)
+            .field("k_addition_defs", &self.k_addition_defs)
+            .field("is_signing", &self.signer.is_some())
+            .finish()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use super::*;
+    use ::{ParserState, Reader, SecurityValidator, SignedContent, Verification, XcsoarSigner, XcsoarValidator};
+
+    /// Signer with long G records, which just hex encodes the content
+    struct HexSigner {
+        content: Vec<u8>,
+    }
+
+    impl Signer for HexSigner {
+        fn update(&mut self, content: &[u8]) {
+            self.content.extend_from_slice(content);
+        }
+
+        fn finish(&mut self) -> String {
+            self.content.iter().map(|b| format!("{:02X}", b)).collect()
+        }
+
+        fn line_length(&self) -> usize {
+            45
+        }
+    }
+
+    const EXAMPLE: &[u8] = b"AFLA6NG\r
+HFDTEDATE:040516,01\r
+HFGIDGLIDERID:D-KCSS\r
+I023638FXA3940SIU\r
+J010810HDT\r
+C040516072924000000000002Task\r
+C0000000N00000000ET\r
+C5049383N00611183E1\r
+D20001\r
+F0810420102\r
+B0810425049315N00610899EA000480023000208\r
+E104533PEV\r
+K081042090\r
+B0810505049315S00610899WV-0048-002300208\r
+LPLTThis flight was my second 1000km attempt\r
+G60DC059E2D2F6CAD2E889224E355DBDDB805CAB10000\r
+";
+
+    #[test]
+    fn test_roundtrip() {
+        let mut writer = Writer::new(Vec::new());
+        for line in Reader::new(Cursor::new(EXAMPLE)) {
+            writer.write(&line.unwrap().record.unwrap()).unwrap();
+        }
+        assert_eq!(writer.into_inner(), EXAMPLE.to_vec());
+    }
+
+    #[test]
+    fn test_write_line() {
+        let input = b"AFLA6NG\nHFPLTPILOT:J\xf6rg\nHFGIDGliderID:D-KCSS\nI013638FXA\nB0810425049315N00610899EA0004800230002";
+        let mut writer = Writer::new(Vec::new());
+        for line in Reader::new(Cursor::new(&input[..])).keep_raw_lines(true) {
+            let mut line = line.unwrap();
+            if let Some(Record::H(ref mut record)) = line.record {
+                if record.code == ::utils::headers::HeaderCode::GID {
+                    record.text = "D-KXXX".into();
+                    line.raw = None;
+                }
+            }
+            writer.write_line(&line).unwrap();
+        }
+        assert_eq!(writer.into_inner(), b"AFLA6NG\nHFPLTPILOT:J\xf6rg\nHFGIDGLIDERID:D-KXXX\r\nI013638FXA\nB0810425049315N00610899EA0004800230002".to_vec());
+    }
+
+    #[test]
+    fn test_signer() {
+        let mut writer = Writer::new(Vec::new()).with_signer(XcsoarSigner::new());
+        for line in Reader::new(Cursor::new(EXAMPLE)) {
+            writer.write(&line.unwrap().record.unwrap()).unwrap();
+        }
+        writer.write_signature().unwrap();
+        let output = writer.into_inner();
+
+        let lines: Vec<_> = output.split(|b| *b == b'\n').collect();
+        assert_eq!(lines.len(), 15 + 8 + 1);
+        assert!(lines[15..23].iter().all(|line| line.len() == 18 && line[0] == b'G'));
+
+        let signed = SignedContent::from_bytes(&output).unwrap();
+        assert_eq!(XcsoarValidator.verify(&signed.content, &signed.signature), Verification::Valid);
+        assert_eq!(XcsoarValidator.signature(&SignedContent::from_bytes(EXAMPLE).unwrap().content),
+                   String::from_utf8(signed.signature).unwrap());
+    }
+
+    #[test]
+    fn test_signer_fixture() {
+        let path = ::std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/2015-05-14-xlk-sem-01.igc");
+        let original = ::std::fs::read(path).unwrap();
+
+        // the raw lines are needed, since formatting the records changes
+        // some of the H and B records of the fixture
+        let mut writer = Writer::new(Vec::new()).with_signer(XcsoarSigner::new());
+        for line in Reader::new(Cursor::new(&original)).keep_raw_lines(true) {
+            writer.write_line(&line.unwrap()).unwrap();
+        }
+        writer.write_signature().unwrap();
+        let output = writer.into_inner();
+
+        // the fixture has LF line endings, but the eight G records are
+        // written with CRLF
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("\r\n").count(), 8);
+        assert_eq!(output.replace("\r\n", "\n").into_bytes(), original);
+    }
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Writer<Vec<u8>>>();
+    }
+
+    #[test]
+    fn test_signer_with_raw_lines() {
+        let input = b"AXXXABC\nHFPLTPILOT:J\xf6rg\nHOCIDCOMPETITIONID:W\nLXXXok\nLPLTno\nG0000\nE104533PEV";
+        let mut writer = Writer::new(Vec::new()).with_signer(HexSigner { content: Vec::new() });
+        for line in Reader::new(Cursor::new(&input[..])).keep_raw_lines(true) {
+            writer.write_line(&line.unwrap()).unwrap();
+        }
+        writer.write_signature().unwrap();
+
+        let mut expected = b"AXXXABC\nHFPLTPILOT:J\xf6rg\nHOCIDCOMPETITIONID:W\nLXXXok\nLPLTno\nE104533PEV\r\n".to_vec();
+        expected.extend_from_slice(b"G415858584142434846504C5450494C4F543A4A72674C5\r\nG858586F6B45313034353333504556\r\n");
+        assert_eq!(writer.into_inner(), expected);
+    }
+
+    #[test]
+    fn test_missing_additions() {
+        let mut writer = Writer::new(Vec::new());
+        writer.write(&Record::parse(b"I013638FXA").unwrap()).unwrap();
+        assert!(writer.write(&Record::parse(b"B0810425049315N00610899EA0004800230").unwrap()).is_err());
+        assert_eq!(writer.get_ref(), b"I013638FXA\r\n");
+    }
+}
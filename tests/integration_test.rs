@@ -1,10 +1,12 @@
 extern crate igc;
 
 use std::path::Path;
-use std::fs::File;
-use std::io::BufReader;
+use std::fs::{self, File};
+use std::io::{BufReader, Cursor, Read};
 
-use igc::Record;
+use igc::{Error, ErrorKind, IgcFile, ParseOptions, ParserState, Record, Reader, Writer, CRecord, LRecordSource, Date, DateTime, Time};
+use igc::utils::additions::{AdditionCode, AdditionSupport};
+use igc::utils::headers::HeaderCode;
 use igc::utils::lines::ByteLinesExt;
 
 #[test]
@@ -52,18 +54,18 @@ fn it_works() {
                 record_count += 1;
 
                 match record {
-                    Record::A => a_record_count += 1,
+                    Record::A(_) => a_record_count += 1,
                     Record::B(_) => b_record_count += 1,
-                    Record::C => c_record_count += 1,
-                    Record::D => d_record_count += 1,
-                    Record::E => e_record_count += 1,
-                    Record::F => f_record_count += 1,
-                    Record::G => g_record_count += 1,
-                    Record::H => h_record_count += 1,
+                    Record::C(_) => c_record_count += 1,
+                    Record::D(_) => d_record_count += 1,
+                    Record::E(_) => e_record_count += 1,
+                    Record::F(_) => f_record_count += 1,
+                    Record::G(_) => g_record_count += 1,
+                    Record::H(_) => h_record_count += 1,
                     Record::I(_) => i_record_count += 1,
-                    Record::J => j_record_count += 1,
-                    Record::K => k_record_count += 1,
-                    Record::L => l_record_count += 1,
+                    Record::J(_) => j_record_count += 1,
+                    Record::K(_) => k_record_count += 1,
+                    Record::L(_) => l_record_count += 1,
                 }
             },
         }
@@ -87,3 +89,261 @@ fn it_works() {
     assert_eq!(k_record_count, 0);
     assert_eq!(l_record_count, 3616);
 }
+
+#[test]
+fn record_payloads() {
+    let path = Path::new(file!())
+        .parent().unwrap()
+        .join("fixtures")
+        .join("654g6ng1.igc");
+
+    let file = File::open(path).unwrap();
+    let reader = BufReader::new(file);
+
+    let records: Vec<_> = reader.byte_lines()
+        .map(|result| result.unwrap())
+        .filter(|bytes| !bytes.is_empty())
+        .map(|bytes| Record::parse(&bytes).unwrap())
+        .collect();
+
+    match records[0] {
+        Record::A(ref record) => {
+            assert_eq!(record.manufacturer, "FLA");
+            assert_eq!(record.id, "6NG");
+            assert_eq!(record.extension, None);
+        },
+        ref record => panic!("unexpected record: {:?}", record),
+    }
+
+    match records[5] {
+        Record::H(ref record) => {
+            assert_eq!(record.code, HeaderCode::GTY);
+            assert_eq!(record.text, "ASG-29E (18m)");
+        },
+        ref record => panic!("unexpected record: {:?}", record),
+    }
+
+    match records[16] {
+        Record::C(CRecord::Declaration(ref record)) => {
+            assert_eq!(record.num_turnpoints, 2);
+            assert_eq!(record.text, Some("Task".into()));
+        },
+        ref record => panic!("unexpected record: {:?}", record),
+    }
+
+    match records[18] {
+        Record::C(CRecord::Turnpoint(ref record)) => {
+            assert_eq!(record.text, Some("1".into()));
+        },
+        ref record => panic!("unexpected record: {:?}", record),
+    }
+
+    let comments: Vec<_> = records.iter()
+        .filter_map(|record| match *record {
+            Record::L(ref record) => Some(record),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(comments[0].source, LRecordSource::Other("FLA".into()));
+    assert_eq!(comments[0].text, "08110003tuwxUXRqtjjuor");
+
+    match records[records.len() - 1] {
+        Record::G(ref record) => assert_eq!(record.text, "0639B49FC5F280A292C990F554789F12381380720000"),
+        ref record => panic!("unexpected record: {:?}", record),
+    }
+}
+
+#[test]
+fn reader_applies_additions() {
+    let path = Path::new(file!())
+        .parent().unwrap()
+        .join("fixtures")
+        .join("654g6ng1.igc");
+
+    let file = File::open(path).unwrap();
+    let reader = Reader::new(BufReader::new(file));
+
+    let fixes: Vec<_> = reader
+        .map(|result| result.unwrap())
+        .filter_map(|line| match line.record {
+            Some(Record::B(record)) => Some((line.line_number, record)),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(fixes.len(), 9762);
+
+    let (line_number, ref fix) = fixes[0];
+    assert_eq!(line_number, 24);
+    assert_eq!(fix.fix_accuracy(), Some(2));
+    assert_eq!(fix.get_addition(&AdditionCode::SIU), Some(&b"08"[..]));
+}
+
+#[test]
+fn igc_file() {
+    let path = Path::new(file!())
+        .parent().unwrap()
+        .join("fixtures")
+        .join("20090715.igc");
+
+    let file = File::open(path).unwrap();
+    let reader = Reader::new(BufReader::new(file));
+
+    // skip the corrupted lines of the fixture
+    let mut file = IgcFile::default();
+    for line in reader.filter_map(Result::ok) {
+        if let Some(record) = line.record {
+            file.push(record, line.datetime);
+        }
+    }
+
+    assert_eq!(file.a_record().unwrap().manufacturer, "LXN");
+    assert_eq!(file.header(HeaderCode::GID).unwrap().text, "EI-GMN");
+    assert_eq!(file.task().unwrap().points.len(), 6);
+    assert_eq!(file.fixes().len(), 3076);
+    assert_eq!(file.fixes()[0].datetime, Some(DateTime::new(Date::new(9, 7, 15), Time::from_hms(11, 31, 35))));
+
+    let datetimes: Vec<_> = file.fixes().iter().map(|fix| fix.datetime.unwrap()).collect();
+    assert!(datetimes.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert_eq!(file.security().len(), 3);
+}
+
+#[test]
+fn tasks() {
+    let path = Path::new(file!())
+        .parent().unwrap()
+        .join("fixtures")
+        .join("654g6ng1.igc");
+
+    let file = IgcFile::parse(BufReader::new(File::open(path).unwrap())).unwrap();
+    let task = file.task().unwrap();
+    assert_eq!(task.declaration.num_turnpoints, 2);
+    assert!(task.is_consistent());
+    assert!(task.takeoff().is_none());
+    assert_eq!(task.start().unwrap().text, Some("1".into()));
+    assert_eq!(task.turnpoints().len(), 2);
+    assert_eq!(task.finish().unwrap().text, Some("4".into()));
+    assert!(task.landing().is_none());
+
+    let path = Path::new(file!())
+        .parent().unwrap()
+        .join("fixtures")
+        .join("2015-05-14-xlk-sem-01.igc");
+
+    let file = IgcFile::parse(BufReader::new(File::open(path).unwrap())).unwrap();
+    let task = file.task().unwrap();
+    assert_eq!(task.declaration.num_turnpoints, 4);
+    assert!(task.is_consistent());
+    assert_eq!(task.takeoff().unwrap().text, Some("TSK_TAKE".into()));
+    assert_eq!(task.start().unwrap().text, Some("004CHAT".into()));
+    assert_eq!(task.turnpoints().len(), 4);
+    assert_eq!(task.finish().unwrap().text, Some("006RDPOI".into()));
+    assert_eq!(task.landing().unwrap().text, Some("TSK_TAKE".into()));
+}
+
+fn records<R: std::io::BufRead>(buf: R) -> Vec<Record> {
+    Reader::new(buf).filter_map(Result::ok).filter_map(|line| line.record).collect()
+}
+
+fn rewrite<R: std::io::BufRead>(buf: R) -> Vec<u8> {
+    let mut writer = Writer::new(Vec::new());
+    for record in records(buf) {
+        writer.write(&record).unwrap();
+    }
+    writer.into_inner()
+}
+
+#[test]
+fn writer_roundtrip() {
+    for name in &["05fd5hv1.igc", "20090715.igc", "2015-05-14-xlk-sem-01.igc", "654g6ng1.igc", "87el0ep1.igc"] {
+        let path = Path::new(file!())
+            .parent().unwrap()
+            .join("fixtures")
+            .join(name);
+
+        let written = rewrite(BufReader::new(File::open(&path).unwrap()));
+        let rewritten = rewrite(Cursor::new(&written));
+        assert_eq!(written, rewritten, "{}", name);
+
+        assert!(Reader::new(Cursor::new(&written)).all(|line| line.is_ok()), "{}", name);
+
+        // the written file contains the same values as the original one
+        let original = records(BufReader::new(File::open(&path).unwrap()));
+        assert_eq!(records(Cursor::new(&written)), original, "{}", name);
+    }
+}
+
+#[test]
+fn lossless_roundtrip() {
+    let fixtures = Path::new(file!()).parent().unwrap().join("fixtures");
+    for entry in fs::read_dir(fixtures).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.display();
+
+        let mut original = Vec::new();
+        File::open(&path).unwrap().read_to_end(&mut original).unwrap();
+
+        // lenient mode returns the corrupted lines of the fixtures without a record
+        let mut writer = Writer::new(Vec::new());
+        for line in Reader::with_options(Cursor::new(&original), ParseOptions::lenient()).keep_raw_lines(true) {
+            match line {
+                Ok(line) => writer.write_line(&line).unwrap(),
+                Err(error) => panic!("{}: {}", name, error),
+            }
+        }
+        assert!(writer.into_inner() == original, "{}", name);
+    }
+}
+
+#[test]
+fn parse_errors() {
+    let path = Path::new(file!())
+        .parent().unwrap()
+        .join("fixtures")
+        .join("20090715.igc");
+
+    let errors: Vec<_> = Reader::new(BufReader::new(File::open(path).unwrap()))
+        .filter_map(|line| match line {
+            Err(Error::Parse(error)) => Some(error),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].kind, ErrorKind::BadCoordinate);
+    assert_eq!(errors[0].line_number, Some(1558));
+    assert_eq!(errors[0].column, Some(16));
+    assert_eq!(errors[0].field.as_deref(), Some("longitude"));
+    assert_eq!(errors[1].kind, ErrorKind::BadAltitude);
+    assert_eq!(errors[1].line_number, Some(1725));
+}
+
+#[test]
+fn parse_modes() {
+    let path = Path::new(file!())
+        .parent().unwrap()
+        .join("fixtures")
+        .join("20090715.igc");
+
+    let file = IgcFile::parse_with_options(BufReader::new(File::open(&path).unwrap()), ParseOptions::lenient()).unwrap();
+    let warnings: Vec<_> = file.warnings().iter().map(|warning| (warning.line_number, warning.kind)).collect();
+    assert_eq!(warnings, vec![
+        (Some(1558), ErrorKind::BadCoordinate),
+        (Some(1725), ErrorKind::BadAltitude),
+    ]);
+
+    let result = IgcFile::parse_with_options(BufReader::new(File::open(&path).unwrap()), ParseOptions::strict());
+    assert!(result.is_err());
+
+    let path = path.with_file_name("654g6ng1.igc");
+    let file = IgcFile::parse_with_options(BufReader::new(File::open(&path).unwrap()), ParseOptions::lenient()).unwrap();
+    assert_eq!(file.fixes().len(), 9762);
+    let warnings: Vec<_> = file.warnings().iter().map(|warning| (warning.line_number, warning.field.as_deref())).collect();
+    assert_eq!(warnings, vec![(Some(5), Some("data source")), (Some(12), Some("long name"))]);
+
+    let path = path.with_file_name("87el0ep1.igc");
+    let file = IgcFile::parse_with_options(BufReader::new(File::open(&path).unwrap()), ParseOptions::strict()).unwrap();
+    assert_eq!(file.fixes().len(), 1068);
+    assert!(file.warnings().is_empty());
+}